@@ -0,0 +1,292 @@
+use std::fmt;
+
+use image::{Rgb, RgbImage};
+
+/// How many points of the complex plane are sampled per pixel, and where
+///
+/// A pixel covers a little square of the complex plane, but sampling it at
+/// just one point makes thin filaments flicker in and out of existence
+/// depending on whether the point happens to land on them (aliasing).
+/// Sampling several points spread over the square and averaging their colors
+/// smooths that out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Supersampling {
+    /// One sample at the pixel's center (see `single_sample_point`)
+    None,
+    /// `n`×`n` samples on a regular grid
+    Grid(u32),
+    /// `n`×`n` samples, each randomly placed within its cell of the grid
+    ///
+    /// The randomness is seeded by the pixel's coordinates, so renders are
+    /// repeatable.
+    Jittered(u32),
+    /// `n`×`n` samples on a grid rotated so that no two samples share a row or
+    /// column, which catches near-horizontal and near-vertical edges better
+    /// than a regular grid with the same number of samples
+    RotatedGrid(u32),
+}
+
+impl Supersampling {
+    /// Returns the number of samples taken per pixel
+    pub fn samples(&self) -> u32 {
+        match *self {
+            Supersampling::None => 1,
+            Supersampling::Grid(n)
+            | Supersampling::Jittered(n)
+            | Supersampling::RotatedGrid(n) => n * n,
+        }
+    }
+
+    /// Returns where to sample the pixel at `pixel_coords`, as fractional pixel
+    /// coordinates
+    pub fn sample_points(&self, pixel_coords: (u32, u32)) -> Vec<(f64, f64)> {
+        let (x, y) = (pixel_coords.0 as f64, pixel_coords.1 as f64);
+
+        // Offsets within the pixel, each in [0, 1)
+        let offsets: Vec<(f64, f64)> = match *self {
+            Supersampling::None => return vec![single_sample_point(pixel_coords)],
+            Supersampling::Grid(n) => {
+                grid_cells(n).map(|(i, j)| {
+                    ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64)
+                }).collect()
+            }
+            Supersampling::Jittered(n) => {
+                grid_cells(n).map(|(i, j)| {
+                    let seed = hash(&[pixel_coords.0, pixel_coords.1, i, j]);
+                    let jitter_x = unit_interval(seed);
+                    let jitter_y = unit_interval(hash(&[seed as u32, (seed >> 32) as u32]));
+
+                    ((i as f64 + jitter_x) / n as f64, (j as f64 + jitter_y) / n as f64)
+                }).collect()
+            }
+            Supersampling::RotatedGrid(n) => {
+                // Each cell's sample goes to a different one of the n² columns
+                // and rows of a finer grid, such that 2×2 gives the classic
+                // rotated grid pattern
+                let fine_cells = (n * n) as f64;
+
+                grid_cells(n).map(|(i, j)| {
+                    (
+                        ((i * n + (n - 1 - j)) as f64 + 0.5) / fine_cells,
+                        ((j * n + i) as f64 + 0.5) / fine_cells
+                    )
+                }).collect()
+            }
+        };
+
+        return offsets.into_iter()
+            .map(|(offset_x, offset_y)| (x + offset_x, y + offset_y))
+            .collect();
+    }
+}
+
+/// Prints the supersampling the way it follows `ss:` in an option, like `3`
+/// or `2:rotated`, or as `none`
+impl fmt::Display for Supersampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Supersampling::None => write!(f, "none"),
+            Supersampling::Grid(n) => write!(f, "{}", n),
+            Supersampling::Jittered(n) => write!(f, "{}:jittered", n),
+            Supersampling::RotatedGrid(n) => write!(f, "{}:rotated", n),
+        }
+    }
+}
+
+/// Returns where to sample the pixel at `pixel_coords` without supersampling,
+/// as fractional pixel coordinates
+///
+/// This is the pixel's center, which is where a 1×1 grid samples it too, so
+/// that every sampling pattern spreads its samples around the same point. It's
+/// half a pixel right of and below the upper left corner
+/// `mandelbrot::pixel_to_complex_point` maps the pixel to, which is where
+/// renders sampled pixels before there was supersampling.
+pub fn single_sample_point(pixel_coords: (u32, u32)) -> (f64, f64) {
+    return (pixel_coords.0 as f64 + 0.5, pixel_coords.1 as f64 + 0.5);
+}
+
+#[test]
+fn test_sample_points() {
+    assert_eq!(Supersampling::None.sample_points((3, 4)), vec![(3.5, 4.5)]);
+    assert_eq!(Supersampling::None.sample_points((3, 4)), vec![single_sample_point((3, 4))]);
+    assert_eq!(
+        Supersampling::None.sample_points((3, 4)),
+        Supersampling::Grid(1).sample_points((3, 4))
+    );
+
+    assert_eq!(
+        Supersampling::Grid(2).sample_points((3, 4)),
+        vec![(3.25, 4.25), (3.75, 4.25), (3.25, 4.75), (3.75, 4.75)]
+    );
+
+    assert_eq!(
+        Supersampling::RotatedGrid(2).sample_points((0, 0)),
+        vec![(0.375, 0.125), (0.875, 0.375), (0.125, 0.625), (0.625, 0.875)]
+    );
+
+    // Jittered samples stay within their cells and are repeatable
+    let jittered = Supersampling::Jittered(3).sample_points((10, 20));
+    assert_eq!(jittered.len(), 9);
+    assert_eq!(jittered, Supersampling::Jittered(3).sample_points((10, 20)));
+
+    for (k, (x, y)) in jittered.iter().enumerate() {
+        let (i, j) = ((k % 3) as f64, (k / 3) as f64);
+
+        assert!(*x >= 10.0 + i / 3.0 && *x < 10.0 + (i + 1.0) / 3.0);
+        assert!(*y >= 20.0 + j / 3.0 && *y < 20.0 + (j + 1.0) / 3.0);
+    }
+}
+
+/// Returns the (column, row) of every cell of an `n`×`n` grid, row by row
+fn grid_cells(n: u32) -> impl Iterator<Item = (u32, u32)> {
+    return (0..n).flat_map(move |j| (0..n).map(move |i| (i, j)));
+}
+
+/// Mixes `values` into a well-scrambled 64-bit number (SplitMix64)
+fn hash(values: &[u32]) -> u64 {
+    let mut h: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    for value in values {
+        h ^= *value as u64;
+        h = h.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+    }
+
+    return h;
+}
+
+/// Maps `h` onto [0, 1)
+fn unit_interval(h: u64) -> f64 {
+    // The top 53 bits fill an f64's mantissa exactly
+    return (h >> 11) as f64 / (1u64 << 53) as f64;
+}
+
+/// Returns the average of `colors`, channel by channel
+pub fn average_colors(colors: &[Rgb<u8>]) -> Rgb<u8> {
+    assert!(!colors.is_empty());
+
+    let mut sums = [0u32; 3];
+
+    for color in colors {
+        for (sum, channel) in sums.iter_mut().zip(color.0.iter()) {
+            *sum += *channel as u32;
+        }
+    }
+
+    let count = colors.len() as u32;
+    let average = |sum: u32| ((sum + count / 2) / count) as u8;
+
+    return Rgb([average(sums[0]), average(sums[1]), average(sums[2])]);
+}
+
+#[test]
+fn test_average_colors() {
+    assert_eq!(average_colors(&[Rgb([10, 20, 30])]), Rgb([10, 20, 30]));
+    assert_eq!(
+        average_colors(&[Rgb([0, 0, 0]), Rgb([255, 255, 255])]),
+        Rgb([128, 128, 128])
+    );
+    assert_eq!(
+        average_colors(&[Rgb([255, 0, 0]), Rgb([0, 255, 0]), Rgb([0, 0, 255]), Rgb([0, 0, 255])]),
+        Rgb([64, 64, 128])
+    );
+}
+
+/// How different neighboring pixels have to be to be considered on an edge
+/// (see `find_edges`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeThreshold {
+    /// The color difference past which pixels are on an edge
+    pub color: u32,
+    /// The escape count difference past which pixels are on an edge, however
+    /// alike their colors are, like when a cycling palette comes back around
+    /// to the same color
+    pub iterations: u32,
+}
+
+/// Edge thresholds low enough to catch the steps between adjacent colors of
+/// most palettes, and the jumps in escape counts around filaments, while
+/// leaving smooth gradients alone
+pub const DEFAULT_EDGE_THRESHOLD: EdgeThreshold = EdgeThreshold { color: 48, iterations: 4 };
+
+/// Returns the coordinates, row by row, of every pixel in `pixels` whose color
+/// or escape count differs from any of its (up to 8) neighbors' by more than
+/// `threshold` says
+///
+/// `iterations` holds each pixel's escape count, row by row, like
+/// `count_iterations` returns them.
+///
+/// The difference between two colors is the sum of the absolute differences
+/// of their channels, so it ranges from 0 (same color) to 765 (black and
+/// white).
+pub fn find_edges(pixels: &RgbImage, iterations: &[u32], threshold: EdgeThreshold) -> Vec<(u32, u32)> {
+    let (width, height) = pixels.dimensions();
+    let mut edges = Vec::new();
+
+    assert_eq!(iterations.len(), width as usize * height as usize);
+
+    let iterations_at = |x: u32, y: u32| iterations[y as usize * width as usize + x as usize];
+
+    for (x, y, pixel) in pixels.enumerate_pixels() {
+        let x_range = x.saturating_sub(1)..=(x + 1).min(width - 1);
+        let pixel_iterations = iterations_at(x, y);
+        let is_edge = (y.saturating_sub(1)..=(y + 1).min(height - 1))
+            .flat_map(|neighbor_y| x_range.clone().map(move |neighbor_x| (neighbor_x, neighbor_y)))
+            .any(|(neighbor_x, neighbor_y)| {
+                color_difference(pixel, pixels.get_pixel(neighbor_x, neighbor_y)) > threshold.color
+                    || pixel_iterations.abs_diff(iterations_at(neighbor_x, neighbor_y)) > threshold.iterations
+            });
+
+        if is_edge {
+            edges.push((x, y));
+        }
+    }
+
+    return edges;
+}
+
+#[test]
+fn test_find_edges() {
+    // A white square with a black pixel in its middle
+    let mut pixels = RgbImage::from_pixel(5, 3, Rgb([255, 255, 255]));
+    let mut iterations = vec![10; 15];
+    let threshold = |color| EdgeThreshold { color, iterations: 4 };
+
+    *pixels.get_pixel_mut(2, 1) = Rgb([0, 0, 0]);
+
+    assert_eq!(
+        find_edges(&pixels, &iterations, threshold(32)),
+        vec![
+            (1, 0), (2, 0), (3, 0),
+            (1, 1), (2, 1), (3, 1),
+            (1, 2), (2, 2), (3, 2),
+        ]
+    );
+
+    // A gray middle pixel is only an edge by lower standards
+    *pixels.get_pixel_mut(2, 1) = Rgb([245, 245, 245]);
+
+    assert_eq!(find_edges(&pixels, &iterations, threshold(32)), vec![]);
+    assert_eq!(find_edges(&pixels, &iterations, threshold(29)).len(), 9);
+
+    // A middle pixel that escaped much later is an edge even when the palette
+    // gives it the same color, but not one that escaped just a little later
+    *pixels.get_pixel_mut(2, 1) = Rgb([255, 255, 255]);
+    iterations[7] = 14;
+
+    assert_eq!(find_edges(&pixels, &iterations, threshold(32)), vec![]);
+
+    iterations[7] = 15;
+
+    assert_eq!(find_edges(&pixels, &iterations, threshold(32)).len(), 9);
+}
+
+/// Returns the sum of the absolute differences of the channels of `a` and `b`
+fn color_difference(a: &Rgb<u8>, b: &Rgb<u8>) -> u32 {
+    return a.0.iter()
+        .zip(b.0.iter())
+        .map(|(a_channel, b_channel)| (*a_channel as i32 - *b_channel as i32).unsigned_abs())
+        .sum();
+}
@@ -0,0 +1,400 @@
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use image::RgbImage;
+use serde::Serialize;
+
+use crate::threadpool::ThreadPool;
+use crate::{Fractal, RenderStrategy, Scene};
+
+/// How to print a benchmark's `Report`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    /// A table for people to read
+    Table,
+    /// Comma-separated values, with a header line
+    Csv,
+    /// A JSON array with an object per measurement
+    Json,
+}
+
+/// A view to render over and over, with every combination of `strategies` and
+/// `thread_counts`, to see how fast each one is
+pub struct Bench<F> {
+    pub scene: Scene<F>,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub strategies: Vec<RenderStrategy>,
+    pub thread_counts: Vec<usize>,
+    /// How many times to render with each combination
+    pub runs: u32,
+}
+
+impl<F: Fractal + Sync> Bench<F> {
+    /// Renders the view `runs` times with each combination of strategy and
+    /// thread count, and returns how long that took
+    ///
+    /// `RenderStrategy::Singlethreaded` only uses one thread anyway, so it's
+    /// only measured once, with one thread, instead of once per thread count.
+    /// It's what speedups are measured against, so it's measured first even if
+    /// `strategies` or `thread_counts` leave it out.
+    pub fn run(&self) -> Report {
+        let mut pixels = RgbImage::new(self.image_width, self.image_height);
+        let mut measurements = vec![
+            self.measure(RenderStrategy::Singlethreaded, &ThreadPool::new(1), &mut pixels)
+        ];
+
+        for &threads in &self.thread_counts {
+            let pool = ThreadPool::new(threads);
+
+            for &strategy in &self.strategies {
+                if strategy == RenderStrategy::Singlethreaded {
+                    continue;
+                }
+
+                measurements.push(self.measure(strategy, &pool, &mut pixels));
+            }
+        }
+
+        return Report {
+            pixels: self.image_width as u64 * self.image_height as u64,
+            measurements,
+        };
+    }
+
+    /// Renders the view `runs` times with `strategy` and the threads of `pool`
+    fn measure(&self, strategy: RenderStrategy, pool: &ThreadPool, pixels: &mut RgbImage) -> Measurement {
+        let threads = pool.size();
+
+        eprintln!("benchmarking {} with {} thread(s)...", strategy, threads);
+
+        let times = (0..self.runs)
+            .map(|_| {
+                let start = Instant::now();
+
+                strategy.render(&self.scene, pixels, pool);
+
+                return start.elapsed();
+            })
+            .collect();
+
+        return Measurement { strategy, threads, times };
+    }
+}
+
+/// Returns thread counts worth benchmarking on this machine: the powers of two
+/// up to the number of threads this process can run in parallel, and that
+/// number itself
+pub fn default_thread_counts() -> Vec<usize> {
+    let available = crate::threadpool::available_threads();
+    let mut counts: Vec<usize> = (0..)
+        .map(|power| 1 << power)
+        .take_while(|&count| count < available)
+        .collect();
+
+    counts.push(available);
+
+    return counts;
+}
+
+#[test]
+fn test_default_thread_counts() {
+    let counts = default_thread_counts();
+
+    assert_eq!(counts[0], 1);
+    assert_eq!(*counts.last().unwrap(), crate::threadpool::available_threads());
+    assert!(counts.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+/// How long rendering took with one strategy and thread count
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub strategy: RenderStrategy,
+    pub threads: usize,
+    /// Wall time of each run
+    pub times: Vec<Duration>,
+}
+
+impl Measurement {
+    /// Returns the median wall time of the runs, which shrugs off the odd run
+    /// slowed down by something else hogging the machine
+    pub fn median(&self) -> Duration {
+        let mut times = self.times.clone();
+
+        times.sort();
+
+        return match times.len() {
+            0 => Duration::from_secs(0),
+            len if len % 2 == 1 => times[len / 2],
+            len => (times[len / 2 - 1] + times[len / 2]) / 2,
+        };
+    }
+}
+
+/// The results of a `Bench`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// How many pixels each render rendered
+    pub pixels: u64,
+    pub measurements: Vec<Measurement>,
+}
+
+impl Report {
+    /// Returns how many pixels per second the median run of `measurement`
+    /// rendered, or `None` if it was too quick to time
+    pub fn pixels_per_second(&self, measurement: &Measurement) -> Option<f64> {
+        let median = measurement.median();
+
+        if median.as_nanos() == 0 {
+            return None;
+        }
+
+        return Some(self.pixels as f64 / median.as_secs_f64());
+    }
+
+    /// Returns how many times faster `measurement` was than singlethreaded
+    /// rendering, or `None` if singlethreaded rendering wasn't measured or
+    /// `measurement` was too quick to time
+    pub fn speedup(&self, measurement: &Measurement) -> Option<f64> {
+        let baseline = self.measurements.iter()
+            .find(|m| m.strategy == RenderStrategy::Singlethreaded)?
+            .median();
+        let median = measurement.median();
+
+        if median.as_nanos() == 0 {
+            return None;
+        }
+
+        return Some(baseline.as_secs_f64() / median.as_secs_f64());
+    }
+
+    /// Returns the report in `format`
+    pub fn format(&self, format: ReportFormat) -> String {
+        let mut out = String::new();
+
+        // Writing to a `String` can't fail, so the `fmt::Result`s are ignored
+        match format {
+            ReportFormat::Table => {
+                let _ = writeln!(
+                    out,
+                    "{:<24} {:>7} {:>12} {:>14} {:>8}",
+                    "strategy", "threads", "median (ms)", "pixels/s", "speedup"
+                );
+
+                for m in &self.measurements {
+                    let pixels_per_second = self.pixels_per_second(m)
+                        .map_or("-".to_string(), |pixels_per_second| format!("{:.0}", pixels_per_second));
+                    let speedup = self.speedup(m)
+                        .map_or("-".to_string(), |speedup| format!("{:.2}x", speedup));
+                    let _ = writeln!(
+                        out,
+                        "{:<24} {:>7} {:>12.1} {:>14} {:>8}",
+                        m.strategy.to_string(),
+                        m.threads,
+                        m.median().as_secs_f64() * 1000.0,
+                        pixels_per_second,
+                        speedup
+                    );
+                }
+            }
+            ReportFormat::Csv => {
+                let _ = writeln!(out, "strategy,threads,runs,median_ms,pixels_per_second,speedup");
+
+                for m in &self.measurements {
+                    let pixels_per_second = self.pixels_per_second(m)
+                        .map_or(String::new(), |pixels_per_second| format!("{:.0}", pixels_per_second));
+                    let speedup = self.speedup(m)
+                        .map_or(String::new(), |speedup| format!("{:.3}", speedup));
+                    let _ = writeln!(
+                        out,
+                        "{},{},{},{:.3},{},{}",
+                        m.strategy,
+                        m.threads,
+                        m.times.len(),
+                        m.median().as_secs_f64() * 1000.0,
+                        pixels_per_second,
+                        speedup
+                    );
+                }
+            }
+            ReportFormat::Json => {
+                let rows: Vec<JsonRow> = self.measurements.iter()
+                    .map(|m| JsonRow {
+                        strategy: m.strategy.to_string(),
+                        threads: m.threads,
+                        times_ms: m.times.iter().map(|&time| milliseconds(time)).collect(),
+                        median_ms: milliseconds(m.median()),
+                        pixels_per_second: self.pixels_per_second(m).map(f64::round),
+                        speedup: self.speedup(m),
+                    })
+                    .collect();
+
+                let _ = writeln!(out, "{}", serde_json::to_string_pretty(&rows).unwrap());
+            }
+        }
+
+        return out;
+    }
+}
+
+/// A `Measurement` as a `Report` prints it in JSON
+///
+/// Renders too quick to time have `null`s for their rate and speedup, since
+/// JSON has no infinity.
+#[derive(Serialize)]
+struct JsonRow {
+    strategy: String,
+    threads: usize,
+    times_ms: Vec<f64>,
+    median_ms: f64,
+    pixels_per_second: Option<f64>,
+    speedup: Option<f64>,
+}
+
+/// Returns `time` in milliseconds
+fn milliseconds(time: Duration) -> f64 {
+    return time.as_nanos() as f64 / 1e6;
+}
+
+#[test]
+fn test_report() {
+    let report = Report {
+        pixels: 1000,
+        measurements: vec![
+            Measurement {
+                strategy: RenderStrategy::Singlethreaded,
+                threads: 1,
+                times: vec![Duration::from_millis(400), Duration::from_millis(100)],
+            },
+            Measurement {
+                strategy: RenderStrategy::PooledRows,
+                threads: 2,
+                times: vec![
+                    Duration::from_millis(200),
+                    Duration::from_millis(100),
+                    Duration::from_millis(125),
+                ],
+            },
+        ],
+    };
+    let rows = &report.measurements[1];
+
+    assert_eq!(report.measurements[0].median(), Duration::from_millis(250));
+    assert_eq!(rows.median(), Duration::from_millis(125));
+    assert_eq!(report.pixels_per_second(rows), Some(8000.0));
+    assert_eq!(report.speedup(rows), Some(2.0));
+
+    assert_eq!(
+        report.format(ReportFormat::Csv),
+        "strategy,threads,runs,median_ms,pixels_per_second,speedup\n\
+        singlethreaded,1,2,250.000,4000,1.000\n\
+        rows,2,3,125.000,8000,2.000\n"
+    );
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&report.format(ReportFormat::Json)).unwrap(),
+        serde_json::json!([
+            {
+                "strategy": "singlethreaded",
+                "threads": 1,
+                "times_ms": [400.0, 100.0],
+                "median_ms": 250.0,
+                "pixels_per_second": 4000.0,
+                "speedup": 1.0
+            },
+            {
+                "strategy": "rows",
+                "threads": 2,
+                "times_ms": [200.0, 100.0, 125.0],
+                "median_ms": 125.0,
+                "pixels_per_second": 8000.0,
+                "speedup": 2.0
+            }
+        ])
+    );
+    assert!(report.format(ReportFormat::Table).lines().nth(2).unwrap().ends_with("2.00x"));
+
+    // Without singlethreaded rendering to compare to, there's no speedup to
+    // speak of, rather than one over something else
+    let report = Report { pixels: 1000, measurements: vec![rows.clone()] };
+
+    assert_eq!(report.speedup(&report.measurements[0]), None);
+    assert!(report.format(ReportFormat::Table).lines().nth(1).unwrap().ends_with(" -"));
+    assert!(report.format(ReportFormat::Csv).ends_with("rows,2,3,125.000,8000,\n"));
+    assert!(report.format(ReportFormat::Json).contains("\"speedup\": null"));
+
+    // Renders too quick to time have no rate or speedup, rather than infinite
+    // ones, which JSON can't even hold
+    let instant = Measurement {
+        strategy: RenderStrategy::PooledRows,
+        threads: 2,
+        times: vec![Duration::from_secs(0)],
+    };
+    let report = Report {
+        pixels: 1000,
+        measurements: vec![report.measurements[0].clone(), instant],
+    };
+    let instant = &report.measurements[1];
+
+    assert_eq!(report.pixels_per_second(instant), None);
+    assert_eq!(report.speedup(instant), None);
+    assert!(report.format(ReportFormat::Csv).ends_with("rows,2,1,0.000,,\n"));
+    let json: serde_json::Value = serde_json::from_str(&report.format(ReportFormat::Json)).unwrap();
+
+    assert_eq!(json[1]["median_ms"], 0.0);
+    assert!(json[1]["pixels_per_second"].is_null());
+    assert!(json[1]["speedup"].is_null());
+    assert!(!report.format(ReportFormat::Table).contains("inf"));
+}
+
+#[test]
+fn test_bench() {
+    let bench = Bench {
+        scene: Scene {
+            fractal: crate::mandelbrot::Mandelbrot,
+            limit: 20,
+            viewport: crate::viewport::Viewport::from_corners(
+                num_complex::Complex { re: -2.0, im: 1.0 },
+                num_complex::Complex { re: 1.0, im: -1.0 }
+            ),
+            coloring: crate::colors::Coloring::new(crate::colors::grayscale()),
+            supersampling: crate::antialiasing::Supersampling::None,
+        },
+        image_width: 6,
+        image_height: 4,
+        strategies: vec![RenderStrategy::Singlethreaded, RenderStrategy::PooledRows],
+        thread_counts: vec![1, 2],
+        runs: 3,
+    };
+    let report = bench.run();
+
+    // Singlethreaded rendering is only measured with one thread
+    let measured: Vec<(RenderStrategy, usize)> = report.measurements.iter()
+        .map(|m| (m.strategy, m.threads))
+        .collect();
+
+    assert_eq!(
+        measured,
+        vec![
+            (RenderStrategy::Singlethreaded, 1),
+            (RenderStrategy::PooledRows, 1),
+            (RenderStrategy::PooledRows, 2),
+        ]
+    );
+    assert_eq!(report.pixels, 24);
+    assert!(report.measurements.iter().all(|m| m.times.len() == 3));
+
+    // ...and always, so there's something to measure speedups against
+    let bench = Bench {
+        strategies: vec![RenderStrategy::PooledRows],
+        thread_counts: vec![2],
+        ..bench
+    };
+    let measured: Vec<(RenderStrategy, usize)> = bench.run().measurements.iter()
+        .map(|m| (m.strategy, m.threads))
+        .collect();
+
+    assert_eq!(
+        measured,
+        vec![(RenderStrategy::Singlethreaded, 1), (RenderStrategy::PooledRows, 2)]
+    );
+}
@@ -6,6 +6,8 @@ color mapping code at: https:github.com/Kate-Painter/BunnyFrac/blob/main/src/col
 #![allow(dead_code)]
 use image::Rgb;
 
+use crate::fractal::{Escape, Orbit};
+
 // Colors
 const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
 const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
@@ -30,27 +32,27 @@ const CYAN: Rgb<u8> = Rgb([0, 255, 255]);
 const TEAL: Rgb<u8> = Rgb([0, 170, 170]);
 
 // Color themes
-pub fn grayscale() -> Vec<Rgb<u8>> {
-    return vec![
+pub fn grayscale() -> Palette {
+    return Palette::new(vec![
         WHITE,
         BLACK
-    ];
+    ]);
 }
 
-pub fn space() -> Vec<Rgb<u8>> {
-    return vec![
+pub fn space() -> Palette {
+    return Palette::new(vec![
         BLACK,
         WHITE
-    ];
+    ]);
 }
 
-pub fn fire() -> Vec<Rgb<u8>> {
+pub fn fire() -> Palette {
     const FADED_ORANGE: Rgb<u8> = Rgb([255, 183, 0]);
     const INTERNATIONAL_ORANGE: Rgb<u8> = Rgb([255, 79, 0]);
     const DARK_RED: Rgb<u8> = Rgb([190, 0, 0]);
     const VERY_DARK_RED: Rgb<u8> = Rgb([128, 0, 0]);
 
-    return vec![
+    return Palette::new(vec![
         YELLOW,
         FADED_ORANGE,
         ORANGE,
@@ -58,75 +60,74 @@ pub fn fire() -> Vec<Rgb<u8>> {
         RED,
         DARK_RED,
         VERY_DARK_RED
-    ];
+    ]);
 }
 
-pub fn k8_peacock() -> Vec<Rgb<u8>> {
-    const K8_KIWI: Rgb<u8> = Rgb([192, 248, 098]);
-    const K8_TEAL: Rgb<u8> = Rgb([016, 166, 144]);
-    const K8_NAVY: Rgb<u8> = Rgb([017, 102, 163]);
-    const K8_MIDNIGHT: Rgb<u8> = Rgb([061, 073, 135]);
-    const K8_SHAKURAS: Rgb<u8> = Rgb([064, 043, 109]);
+pub fn k8_peacock() -> Palette {
+    const K8_KIWI: Rgb<u8> = Rgb([192, 248,  98]);
+    const K8_TEAL: Rgb<u8> = Rgb([ 16, 166, 144]);
+    const K8_NAVY: Rgb<u8> = Rgb([ 17, 102, 163]);
+    const K8_MIDNIGHT: Rgb<u8> = Rgb([ 61,  73, 135]);
+    const K8_SHAKURAS: Rgb<u8> = Rgb([ 64,  43, 109]);
 
-    return vec![
+    return Palette::new(vec![
         K8_KIWI,
         K8_TEAL,
         K8_NAVY,
         K8_MIDNIGHT,
         K8_SHAKURAS
-    ];
+    ]);
 }
 
-pub fn usa() -> Vec<Rgb<u8>> {
-    return vec![
+pub fn usa() -> Palette {
+    return Palette::new(vec![
         RED,
         WHITE,
         BLUE
-    ];
+    ]);
 }
 
-pub fn raspberry_acid() -> Vec<Rgb<u8>> {
+pub fn raspberry_acid() -> Palette {
     const RASPBERRY: Rgb<u8> = Rgb([238, 107, 107]);
 
-    return vec![
+    return Palette::new(vec![
         BLACK,
         RASPBERRY,
         CYAN
-    ];
+    ]);
 }
 
-pub fn mojave() -> Vec<Rgb<u8>> {
+pub fn mojave() -> Palette {
     const TAN: Rgb<u8> = Rgb([240, 211, 169]);
     const DARK_RED: Rgb<u8> = Rgb([163, 65, 52]);
     const NAVY: Rgb<u8> = Rgb([77, 77, 170]);
     const SKY: Rgb<u8> = Rgb([130, 184, 170]);
 
-    return vec![
+    return Palette::new(vec![
         ORANGE,
         TAN,
         DARK_RED,
         NAVY,
         SKY
-    ];
+    ]);
 }
 
-pub fn houndeye() -> Vec<Rgb<u8>> {
+pub fn houndeye() -> Palette {
     const DORSAL_YELLOW: Rgb<u8> = Rgb([159, 149, 41]);
     const VENTRAL_TAN: Rgb<u8> = Rgb([201, 197, 162]);
     const BLUE_STREAK: Rgb<u8> = Rgb([16, 131, 153]);
     const EYELID_RIM: Rgb<u8> = Rgb([142, 84, 72]);
 
-    return vec![
-        DORSAL_YELLOW,
-        DORSAL_YELLOW,
-        VENTRAL_TAN,
-        VENTRAL_TAN,
-        BLUE_STREAK,
-        VENTRAL_TAN,
-        VENTRAL_TAN,
-        EYELID_RIM,
-        EYELID_RIM
-    ];
+    // The end colors hold past the first and last bands by themselves, so the
+    // gradient still looks the way it did when the theme repeated colors to
+    // weight them
+    return Palette::from_bands(&[
+        (DORSAL_YELLOW, 0.125, 0.125),
+        (VENTRAL_TAN,   0.25,  0.375),
+        (BLUE_STREAK,   0.5,   0.5),
+        (VENTRAL_TAN,   0.625, 0.75),
+        (EYELID_RIM,    0.875, 0.875)
+    ]);
 }
 
 /// Translates `a_channel` toward `b_channel` by `degree` percent
@@ -141,16 +142,14 @@ fn blend_color_channel(a_channel: u8, b_channel: u8, degree: f64) -> u8 {
         return a_channel;
     }
     else {
-        let delta: u8 = (a_channel as i32 - b_channel as i32).abs() as u8;
+        let delta: u8 = (a_channel as i32 - b_channel as i32).unsigned_abs() as u8;
         let blended_delta: u8 = (delta as f64 * degree).round() as u8;
-        let blended_a: u8;
-
-        if a_channel <= b_channel {
-            blended_a = (a_channel + blended_delta) as u8;
+        let blended_a: u8 = if a_channel <= b_channel {
+            a_channel + blended_delta
         }
         else {
-            blended_a = (a_channel - blended_delta) as u8;
-        }
+            a_channel - blended_delta
+        };
 
         return blended_a;
     }
@@ -283,13 +282,311 @@ fn test_blend_colors() {
     );
 }
 
-/// Returns the color in `palette` that maps onto `iterations`
+/// The color space colors are blended in, which decides what the colors
+/// between two colors of a palette are
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Straight between the colors' sRGB channels, like `blend_colors`, which
+    /// goes dark and muddy between very different colors
+    Srgb,
+    /// Straight between the amounts of red, green and blue light, which keeps
+    /// blends between bright colors bright
+    LinearRgb,
+    /// The short way around the color wheel, with saturation and value in
+    /// between, which goes through the colors of the rainbow between them
+    Hsv,
+    /// Like `Hsv`, but with lightness instead of value
+    Hsl,
+    /// Straight through the OKLab color space, where equal steps look about
+    /// equally different, so gradients look even
+    Oklab,
+}
+
+impl Interpolation {
+    /// Returns a color made by blending the color `a` into color `b` by the
+    /// given `degree`, in this color space
+    pub fn blend(self, a: &Rgb<u8>, b: &Rgb<u8>, degree: f64) -> Rgb<u8> {
+        if degree <= 0.0 {
+            return *a;
+        }
+        else if degree >= 1.0 {
+            return *b;
+        }
+
+        match self {
+            Interpolation::Srgb => blend_colors(a, b, degree),
+            Interpolation::LinearRgb => {
+                linear_to_srgb(lerp(srgb_to_linear(a), srgb_to_linear(b), degree))
+            }
+            Interpolation::Hsv => hsv_to_srgb(blend_hues(srgb_to_hsv(a), srgb_to_hsv(b), degree)),
+            Interpolation::Hsl => hsl_to_srgb(blend_hues(srgb_to_hsl(a), srgb_to_hsl(b), degree)),
+            Interpolation::Oklab => {
+                let (a, b) = (linear_to_oklab(srgb_to_linear(a)), linear_to_oklab(srgb_to_linear(b)));
+
+                linear_to_srgb(oklab_to_linear(lerp(a, b, degree)))
+            }
+        }
+    }
+}
+
+/// Prints the color space the way `parse_interpolation` parses it
+impl std::fmt::Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match *self {
+            Interpolation::Srgb => "srgb",
+            Interpolation::LinearRgb => "linear",
+            Interpolation::Hsv => "hsv",
+            Interpolation::Hsl => "hsl",
+            Interpolation::Oklab => "oklab",
+        };
+
+        return write!(f, "{}", name);
+    }
+}
+
+#[test]
+fn test_interpolation() {
+    let gray = Rgb([128, 128, 128]);
+
+    for &interpolation in &[
+        Interpolation::Srgb,
+        Interpolation::LinearRgb,
+        Interpolation::Hsv,
+        Interpolation::Hsl,
+        Interpolation::Oklab,
+    ] {
+        // The ends are the colors themselves, and blending a color with
+        // itself doesn't change it
+        assert_eq!(interpolation.blend(&RED, &BLUE, 0.0), RED);
+        assert_eq!(interpolation.blend(&RED, &BLUE, 1.0), BLUE);
+        assert_eq!(interpolation.blend(&TEAL, &TEAL, 0.3), TEAL);
+        assert_eq!(interpolation.blend(&gray, &gray, 0.7), gray);
+    }
+
+    // sRGB is the plain old blend
+    assert_eq!(Interpolation::Srgb.blend(&RED, &BLUE, 0.5), blend_colors(&RED, &BLUE, 0.5));
+
+    // Linear light is brighter in the middle
+    assert_eq!(Interpolation::LinearRgb.blend(&RED, &BLUE, 0.5), Rgb([188, 0, 188]));
+
+    // Hues take the short way around, from red (0°) back through magenta
+    // (300°) to blue (240°)...
+    assert_eq!(Interpolation::Hsv.blend(&RED, &BLUE, 0.5), SHAKTURI_VIOLET);
+    assert_eq!(Interpolation::Hsl.blend(&RED, &BLUE, 0.5), SHAKTURI_VIOLET);
+    assert_eq!(Interpolation::Hsv.blend(&RED, &YELLOW, 0.5), Rgb([255, 128, 0]));
+
+    // ...and grays, which have no hue, take on the other color's
+    assert_eq!(Interpolation::Hsv.blend(&gray, &RED, 0.5), Rgb([192, 96, 96]));
+    assert_eq!(Interpolation::Hsl.blend(&WHITE, &BLUE, 0.5), Rgb([159, 159, 223]));
+
+    // OKLab is perceptually halfway between black and white, which is darker
+    // than halfway between their channels
+    assert_eq!(Interpolation::Oklab.blend(&BLACK, &WHITE, 0.5), Rgb([99, 99, 99]));
+}
+
+/// Returns `a` blended into `b` by `degree`, component by component
+fn lerp(a: [f64; 3], b: [f64; 3], degree: f64) -> [f64; 3] {
+    return [
+        a[0] + (b[0] - a[0]) * degree,
+        a[1] + (b[1] - a[1]) * degree,
+        a[2] + (b[2] - a[2]) * degree,
+    ];
+}
+
+/// Returns the channels of `color`, from 0 to 1
+fn srgb_to_unit(color: &Rgb<u8>) -> [f64; 3] {
+    return [color[0] as f64 / 255.0, color[1] as f64 / 255.0, color[2] as f64 / 255.0];
+}
+
+/// Returns the color with the channels `unit`, from 0 to 1, rounded to bytes
+fn unit_to_srgb(unit: [f64; 3]) -> Rgb<u8> {
+    let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    return Rgb([channel(unit[0]), channel(unit[1]), channel(unit[2])]);
+}
+
+/// Returns the amounts of red, green and blue light in `color`, undoing sRGB's
+/// gamma curve
+fn srgb_to_linear(color: &Rgb<u8>) -> [f64; 3] {
+    let linear = |c: f64| {
+        if c <= 0.04045 {
+            return c / 12.92;
+        }
+
+        return ((c + 0.055) / 1.055).powf(2.4);
+    };
+    let [r, g, b] = srgb_to_unit(color);
+
+    return [linear(r), linear(g), linear(b)];
+}
+
+/// Returns the sRGB color with the amounts of red, green and blue light in
+/// `linear`
+fn linear_to_srgb(linear: [f64; 3]) -> Rgb<u8> {
+    let gamma = |c: f64| {
+        if c <= 0.0031308 {
+            return 12.92 * c;
+        }
+
+        return 1.055 * c.powf(1.0 / 2.4) - 0.055;
+    };
+
+    return unit_to_srgb([gamma(linear[0]), gamma(linear[1]), gamma(linear[2])]);
+}
+
+/// Returns the OKLab coordinates (lightness, green-red, blue-yellow) of the
+/// linear light `linear`
+///
+/// See https://bottosson.github.io/posts/oklab/ for where the numbers come
+/// from.
+fn linear_to_oklab([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    return [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ];
+}
+
+/// Returns the linear light at the OKLab coordinates `oklab`
+fn oklab_to_linear([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    return [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ];
+}
+
+/// Returns the hue (in degrees, from 0 up to 360) of `color`, along with its
+/// largest and smallest channels, from 0 to 1
+fn hue_and_extremes(color: &Rgb<u8>) -> (f64, f64, f64) {
+    let [r, g, b] = srgb_to_unit(color);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    }
+    else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    }
+    else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    }
+    else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    return (hue, max, min);
+}
+
+/// Returns the color with `hue` (in degrees) and `chroma` (its largest
+/// channel minus its smallest), with `min` added to every channel
+fn hue_to_srgb(hue: f64, chroma: f64, min: f64) -> Rgb<u8> {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    return unit_to_srgb([r + min, g + min, b + min]);
+}
+
+/// Returns the hue, saturation and value of `color`
+fn srgb_to_hsv(color: &Rgb<u8>) -> [f64; 3] {
+    let (hue, max, min) = hue_and_extremes(color);
+    let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+
+    return [hue, saturation, max];
+}
+
+/// Returns the color with the hue, saturation and value `hsv`
+fn hsv_to_srgb([hue, saturation, value]: [f64; 3]) -> Rgb<u8> {
+    let chroma = value * saturation;
+
+    return hue_to_srgb(hue, chroma, value - chroma);
+}
+
+/// Returns the hue, saturation and lightness of `color`
+fn srgb_to_hsl(color: &Rgb<u8>) -> [f64; 3] {
+    let (hue, max, min) = hue_and_extremes(color);
+    let lightness = (max + min) / 2.0;
+    let saturation = if max == min {
+        0.0
+    }
+    else {
+        (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+    };
+
+    return [hue, saturation, lightness];
+}
+
+/// Returns the color with the hue, saturation and lightness `hsl`
+fn hsl_to_srgb([hue, saturation, lightness]: [f64; 3]) -> Rgb<u8> {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+
+    return hue_to_srgb(hue, chroma, lightness - chroma / 2.0);
+}
+
+/// Returns `a` blended into `b` by `degree`, where both are a hue (in degrees)
+/// followed by a saturation and a value or lightness
+///
+/// The hue goes the short way around the color wheel, and a color without any
+/// saturation (a gray), whose hue means nothing, takes on the other's hue, so
+/// blends between a gray and a color don't wander through other colors.
+fn blend_hues(a: [f64; 3], b: [f64; 3], degree: f64) -> [f64; 3] {
+    let (mut a_hue, mut b_hue) = (a[0], b[0]);
+
+    if a[1] == 0.0 {
+        a_hue = b_hue;
+    }
+    else if b[1] == 0.0 {
+        b_hue = a_hue;
+    }
+
+    let mut hue_difference = b_hue - a_hue;
+
+    if hue_difference > 180.0 {
+        hue_difference -= 360.0;
+    }
+    else if hue_difference < -180.0 {
+        hue_difference += 360.0;
+    }
+
+    let [_, saturation, value] = lerp(a, b, degree);
+
+    return [a_hue + hue_difference * degree, saturation, value];
+}
+
+/// Returns the color in `palette` that maps onto `iterations`, blending its
+/// colors in sRGB
 ///
-/// When `iterations` is equal to `limit`, this always returns black.
-pub fn iterations_to_color(
+/// When `iterations` is equal to `limit`, this returns black. `Coloring`
+/// doesn't get that far with such points, and colors them by its `inside` and
+/// `interior` instead.
+pub fn iterations_to_color(iterations: u32, limit: u32, palette: &[Rgb<u8>]) -> Rgb<u8> {
+    return iterations_to_color_with(iterations, limit, palette, Interpolation::Srgb);
+}
+
+/// Returns the color in `palette` that maps onto `iterations`, like
+/// `iterations_to_color`, but blending its colors with `interpolation`
+pub fn iterations_to_color_with(
     iterations: u32,
     limit: u32,
-    palette: &Vec<Rgb<u8>>
+    palette: &[Rgb<u8>],
+    interpolation: Interpolation
 ) -> Rgb<u8> {
     assert!(palette.len() > 1); // We need at least 2 colors
     
@@ -379,7 +676,760 @@ pub fn iterations_to_color(
     }
 
     // Return color in subrange gradient
-    return blend_colors(&palette[start_color], &palette[next_color], subrange_cover);
+    return interpolation.blend(&palette[start_color], &palette[next_color], subrange_cover);
+}
+
+/// Returns the color in `palette` that maps onto the fractional `iterations`,
+/// blending its colors in sRGB
+///
+/// Unlike `iterations_to_color`, which can only land on one of `limit` spots of
+/// the palette's gradient, this lands anywhere on it, so continuous iteration
+/// counts (see `Escape::smooth_iterations`) give continuous colors.
+///
+/// When `iterations` is `limit` or more, this returns black. `Coloring` doesn't
+/// get that far with such points, and colors them by its `inside` and
+/// `interior` instead.
+pub fn smooth_iterations_to_color(iterations: f64, limit: u32, palette: &[Rgb<u8>]) -> Rgb<u8> {
+    return smooth_iterations_to_color_with(iterations, limit, palette, Interpolation::Srgb);
+}
+
+/// Returns the color in `palette` that maps onto the fractional `iterations`,
+/// like `smooth_iterations_to_color`, but blending its colors with
+/// `interpolation`
+pub fn smooth_iterations_to_color_with(
+    iterations: f64,
+    limit: u32,
+    palette: &[Rgb<u8>],
+    interpolation: Interpolation
+) -> Rgb<u8> {
+    assert!(palette.len() > 1); // We need at least 2 colors
+
+    if iterations >= limit as f64 {
+        return BLACK;
+    }
+
+    // Same subranges as in `iterations_to_color`, but with fractional coverage
+    let subranges = palette.len() - 1;
+    let range_cover = iterations.max(0.0) / limit as f64 * subranges as f64;
+    let start_color: usize = (range_cover.floor() as usize).min(subranges - 1);
+    let next_color: usize = start_color + 1;
+    let subrange_cover = range_cover - start_color as f64;
+
+    return interpolation.blend(&palette[start_color], &palette[next_color], subrange_cover);
+}
+
+#[test]
+fn test_smooth_iterations_to_color() {
+    let palette: Vec<Rgb<u8>> = vec![RED, GREEN, BLUE];
+    let limit = 100;
+
+    assert_eq!(smooth_iterations_to_color(0.0, limit, &palette), RED);
+    assert_eq!(smooth_iterations_to_color(50.0, limit, &palette), GREEN);
+    assert_eq!(smooth_iterations_to_color(100.0, limit, &palette), BLACK);
+
+    assert_eq!(smooth_iterations_to_color(25.0, limit, &palette), Rgb([127, 128, 0]));
+    assert_eq!(smooth_iterations_to_color(75.0, limit, &palette), Rgb([0, 127, 128]));
+
+    // Fractions between two integer counts land between their colors
+    let before = smooth_iterations_to_color(10.0, limit, &palette);
+    let between = smooth_iterations_to_color(10.5, limit, &palette);
+    let after = smooth_iterations_to_color(11.0, limit, &palette);
+
+    assert!(before[0] > between[0] && between[0] > after[0]);
+    assert!(before[1] < between[1] && between[1] < after[1]);
+
+    // Just short of the limit is almost the last color
+    assert_eq!(smooth_iterations_to_color(99.9, limit, &palette), Rgb([0, 1, 254]));
+
+    // Other interpolations blend the same colors another way
+    assert_eq!(
+        smooth_iterations_to_color_with(25.0, limit, &palette, Interpolation::Hsv),
+        Interpolation::Hsv.blend(&RED, &GREEN, 0.5)
+    );
+    assert_eq!(
+        iterations_to_color_with(25, limit, &palette, Interpolation::Hsv),
+        Interpolation::Hsv.blend(&RED, &GREEN, 0.5)
+    );
+}
+
+/// Returns the color `fraction` (from 0 to 1) of the way along the gradient
+/// through `colors`, where each color sits at the same index of `positions`
+///
+/// Before the first position is all the first color, and after the last is
+/// all the last color.
+pub fn gradient_color(
+    fraction: f64,
+    colors: &[Rgb<u8>],
+    positions: &[f64],
+    interpolation: Interpolation
+) -> Rgb<u8> {
+    assert!(colors.len() > 1 && colors.len() == positions.len());
+
+    let next_color = positions.iter()
+        .position(|&position| position > fraction)
+        .unwrap_or(positions.len());
+
+    if next_color == 0 {
+        return colors[0];
+    }
+
+    if next_color == positions.len() {
+        return colors[colors.len() - 1];
+    }
+
+    // `fraction` is between the two positions, which can't be equal
+    let start_color = next_color - 1;
+    let subrange_cover = (fraction - positions[start_color])
+        / (positions[next_color] - positions[start_color]);
+
+    return interpolation.blend(&colors[start_color], &colors[next_color], subrange_cover);
+}
+
+#[test]
+fn test_gradient_color() {
+    let colors = vec![RED, GREEN, BLUE];
+    let positions = vec![0.0, 0.8, 1.0];
+
+    assert_eq!(gradient_color(0.0, &colors, &positions, Interpolation::Srgb), RED);
+    assert_eq!(gradient_color(0.4, &colors, &positions, Interpolation::Srgb), blend_colors(&RED, &GREEN, 0.5));
+    assert_eq!(gradient_color(0.8, &colors, &positions, Interpolation::Srgb), GREEN);
+    assert_eq!(gradient_color(0.9, &colors, &positions, Interpolation::Srgb), blend_colors(&GREEN, &BLUE, 0.5));
+    assert_eq!(gradient_color(1.0, &colors, &positions, Interpolation::Srgb), BLUE);
+
+    // Outside the stops is all the nearest stop's color
+    let positions = vec![0.25, 0.5, 0.75];
+
+    assert_eq!(gradient_color(0.1, &colors, &positions, Interpolation::Srgb), RED);
+    assert_eq!(gradient_color(0.9, &colors, &positions, Interpolation::Srgb), BLUE);
+
+    // Stops at the same position make a hard edge
+    let positions = vec![0.0, 0.5, 0.5];
+
+    assert_eq!(gradient_color(0.49, &colors, &positions, Interpolation::Srgb), blend_colors(&RED, &GREEN, 0.98));
+    assert_eq!(gradient_color(0.5, &colors, &positions, Interpolation::Srgb), BLUE);
+}
+
+/// The colors to blend between, from fastest- to slowest-escaping points, and
+/// where along the gradient between them each one sits
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub colors: Vec<Rgb<u8>>,
+    /// Where each color sits along the gradient, from 0 to 1 in increasing
+    /// order, or `None` to spread the colors out evenly
+    pub positions: Option<Vec<f64>>,
+    /// The color space the colors are blended in
+    pub interpolation: Interpolation,
+}
+
+impl Palette {
+    /// Creates a palette with its colors spread out evenly, and blended in
+    /// sRGB
+    pub fn new(colors: Vec<Rgb<u8>>) -> Palette {
+        return Palette { colors, positions: None, interpolation: Interpolation::Srgb };
+    }
+
+    /// Creates a palette from (color, position) stops, blended in sRGB
+    ///
+    /// Positions go from 0 to 1 in increasing order, and the gap between two
+    /// stops is how much of the gradient blending their colors takes up.
+    pub fn from_stops(stops: &[(Rgb<u8>, f64)]) -> Palette {
+        assert!(stops.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(stops.iter().all(|(_, position)| (0.0..=1.0).contains(position)));
+
+        return Palette {
+            colors: stops.iter().map(|(color, _)| *color).collect(),
+            positions: Some(stops.iter().map(|(_, position)| *position).collect()),
+            interpolation: Interpolation::Srgb
+        };
+    }
+
+    /// Creates a palette from (color, start, end) bands, blended in sRGB
+    ///
+    /// Each color holds from its band's start to its end, and blends into the
+    /// next band's color from there. A band that starts where it ends is just
+    /// a stop, like those of `from_stops`.
+    pub fn from_bands(bands: &[(Rgb<u8>, f64, f64)]) -> Palette {
+        let stops: Vec<(Rgb<u8>, f64)> = bands.iter()
+            .flat_map(|&(color, start, end)| {
+                let end_stop = if end > start { Some((color, end)) } else { None };
+
+                return std::iter::once((color, start)).chain(end_stop);
+            })
+            .collect();
+
+        return Palette::from_stops(&stops);
+    }
+
+    /// Returns the color `fraction` (from 0 to 1) of the way along the
+    /// palette's gradient
+    pub fn color_at(&self, fraction: f64) -> Rgb<u8> {
+        if let Some(positions) = &self.positions {
+            return gradient_color(fraction, &self.colors, positions, self.interpolation);
+        }
+
+        // Same subranges as in `smooth_iterations_to_color`
+        let subranges = self.colors.len() - 1;
+        let range_cover = fraction.clamp(0.0, 1.0) * subranges as f64;
+        let start_color = (range_cover.floor() as usize).min(subranges - 1);
+
+        return self.interpolation.blend(
+            &self.colors[start_color],
+            &self.colors[start_color + 1],
+            range_cover - start_color as f64
+        );
+    }
+
+    /// Returns the color `fraction` (from 0 to 1) of the way around the
+    /// palette's gradient, wrapped around so that its last color blends back
+    /// into its first
+    ///
+    /// Evenly spread colors leave as much of the gradient for that blend as
+    /// between any other two, and positioned ones leave what's past their
+    /// last stop (and before their first one).
+    pub fn cyclic_color(&self, fraction: f64) -> Rgb<u8> {
+        let colors = &self.colors;
+        let last = colors.len() - 1;
+
+        let positions = match &self.positions {
+            Some(positions) => positions,
+            None => {
+                let range_cover = fraction * colors.len() as f64;
+                let start_color = (range_cover.floor() as usize).min(last);
+                let next_color = (start_color + 1) % colors.len();
+
+                return self.interpolation.blend(
+                    &colors[start_color],
+                    &colors[next_color],
+                    range_cover - start_color as f64
+                );
+            }
+        };
+
+        if fraction >= positions[0] && fraction < positions[last] {
+            return gradient_color(fraction, colors, positions, self.interpolation);
+        }
+
+        // Past the last stop, toward the first one a cycle later
+        let wrap_width = positions[0] + 1.0 - positions[last];
+
+        if wrap_width <= 0.0 {
+            return colors[last];
+        }
+
+        let wrap_cover = (fraction - positions[last]).rem_euclid(1.0) / wrap_width;
+
+        return self.interpolation.blend(&colors[last], &colors[0], wrap_cover);
+    }
+}
+
+#[test]
+fn test_palette_stops() {
+    // Red to green takes up 80% of the gradient, with no need to repeat colors
+    let coloring = Coloring::new(Palette::from_stops(&[(RED, 0.0), (GREEN, 0.8), (BLUE, 1.0)]));
+    let escape = |iterations| Escape { iterations, norm_sqr: 4.0 };
+
+    assert_eq!(coloring.color(&escape(0), 100), RED);
+    assert_eq!(coloring.color(&escape(40), 100), blend_colors(&RED, &GREEN, 0.5));
+    assert_eq!(coloring.color(&escape(80), 100), GREEN);
+    assert_eq!(coloring.color(&escape(90), 100), blend_colors(&GREEN, &BLUE, 0.5));
+    assert_eq!(coloring.color(&escape(100), 100), BLACK);
+
+    // The built-in themes with stops keep them
+    assert!(houndeye().positions.is_some());
+}
+
+#[test]
+fn test_palette_bands() {
+    // Green holds from 40% to 60% of the gradient, between blends
+    let palette = Palette::from_bands(&[(RED, 0.0, 0.0), (GREEN, 0.4, 0.6), (BLUE, 1.0, 1.0)]);
+
+    assert_eq!(palette, Palette::from_stops(&[(RED, 0.0), (GREEN, 0.4), (GREEN, 0.6), (BLUE, 1.0)]));
+    assert_eq!(palette.color_at(0.2), blend_colors(&RED, &GREEN, 0.5));
+    assert_eq!(palette.color_at(0.4), GREEN);
+    assert_eq!(palette.color_at(0.5), GREEN);
+    assert_eq!(palette.color_at(0.6), GREEN);
+    assert_eq!(palette.color_at(0.8), blend_colors(&GREEN, &BLUE, 0.5));
+}
+
+#[test]
+fn test_houndeye_stops() {
+    // The nine evenly spread colors the theme used to be
+    let dorsal_yellow = Rgb([159, 149, 41]);
+    let ventral_tan = Rgb([201, 197, 162]);
+    let blue_streak = Rgb([16, 131, 153]);
+    let eyelid_rim = Rgb([142, 84, 72]);
+    let old_colors = vec![
+        dorsal_yellow,
+        dorsal_yellow,
+        ventral_tan,
+        ventral_tan,
+        blue_streak,
+        ventral_tan,
+        ventral_tan,
+        eyelid_rim,
+        eyelid_rim
+    ];
+
+    // Its stops color every escape time the way those did, at limits that
+    // split evenly between them. An odd number of iterations between colors
+    // keeps blends off exact halves, which float error could round either way
+    let coloring = Coloring::new(houndeye());
+
+    for &limit in &[200, 1000] {
+        for iterations in 0..=limit {
+            let escape = Escape { iterations, norm_sqr: 4.0 };
+
+            assert_eq!(
+                coloring.color(&escape, limit),
+                iterations_to_color(iterations, limit, &old_colors),
+                "{} of {} iterations",
+                iterations,
+                limit
+            );
+        }
+    }
+
+    // At limits that don't split evenly, like the default, the old theme
+    // rounded each color's share of the iterations to a whole number, so its
+    // colors sit a little off the stops, and blends come out a few shades off
+    let limit = crate::DEFAULT_ITERATIONS;
+
+    for iterations in 0..=limit {
+        let escape = Escape { iterations, norm_sqr: 4.0 };
+        let new = coloring.color(&escape, limit);
+        let old = iterations_to_color(iterations, limit, &old_colors);
+
+        assert!(
+            color_difference_within(&new, &old, 8),
+            "{:?} and {:?} at {} of {} iterations",
+            new,
+            old,
+            iterations,
+            limit
+        );
+    }
+}
+
+/// Returns whether no channel of `a` is more than `tolerance` off from `b`'s
+#[cfg(test)]
+fn color_difference_within(a: &Rgb<u8>, b: &Rgb<u8>, tolerance: i32) -> bool {
+    return a.0.iter()
+        .zip(b.0.iter())
+        .all(|(a_channel, b_channel)| (*a_channel as i32 - *b_channel as i32).abs() <= tolerance);
+}
+
+#[test]
+fn test_cyclic_color() {
+    let palette = Palette::new(vec![RED, GREEN, BLUE]);
+
+    assert_eq!(palette.cyclic_color(0.0), RED);
+    assert_eq!(palette.cyclic_color(1.0 / 3.0), GREEN);
+    assert_eq!(palette.cyclic_color(0.5), blend_colors(&GREEN, &BLUE, 0.5));
+    assert_eq!(palette.cyclic_color(5.0 / 6.0), blend_colors(&BLUE, &RED, 0.5));
+
+    // Positioned stops wrap around through the gap past the last one
+    let palette = Palette::from_stops(&[(RED, 0.25), (BLUE, 0.75)]);
+
+    assert_eq!(palette.cyclic_color(0.5), blend_colors(&RED, &BLUE, 0.5));
+    assert_eq!(palette.cyclic_color(0.0), blend_colors(&BLUE, &RED, 0.5));
+    assert_eq!(palette.cyclic_color(0.9), blend_colors(&BLUE, &RED, 0.3));
+    assert_eq!(palette.cyclic_color(0.25), RED);
+}
+
+/// How often the palette repeats, for coloring deep zooms, whose iteration
+/// counts are all crammed into a sliver of the range up to the limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cycle {
+    /// How many iterations it takes to go around the palette once, or if
+    /// `log`, how much the natural log of the iteration count has to grow by
+    pub period: f64,
+    /// Whether to cycle by the log of the iteration count, so that the palette
+    /// repeats less and less often as points take longer to escape
+    pub log: bool,
+    /// How far around the palette to start, from 0 to 1
+    pub offset: f64,
+}
+
+impl Cycle {
+    /// Returns how far around the palette, from 0 to 1, `iterations` lands
+    pub fn fraction(&self, iterations: f64) -> f64 {
+        let cycles = if self.log {
+            iterations.max(0.0).ln_1p() / self.period
+        }
+        else {
+            iterations / self.period
+        };
+
+        return (cycles + self.offset).rem_euclid(1.0);
+    }
+}
+
+/// Prints the cycle the way it follows `cycle:` in an option, like `32`,
+/// `32:0.5` or `log:0.5`
+impl std::fmt::Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.log {
+            write!(f, "log:")?;
+        }
+
+        write!(f, "{}", self.period)?;
+
+        if self.offset != 0.0 {
+            write!(f, ":{}", self.offset)?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[test]
+fn test_cycle() {
+    let cycle = Cycle { period: 10.0, log: false, offset: 0.0 };
+
+    assert_eq!(cycle.fraction(0.0), 0.0);
+    assert_eq!(cycle.fraction(25.0), 0.5);
+    assert_eq!(cycle.fraction(1000.0), 0.0);
+
+    let cycle = Cycle { offset: 0.75, ..cycle };
+
+    assert_eq!(cycle.fraction(5.0), 0.25);
+
+    // Each doubling of iterations + 1 goes around once, so 1023 comes all the
+    // way around (or a hair short of it)
+    let cycle = Cycle { period: 2f64.ln(), log: true, offset: 0.0 };
+
+    assert_eq!(cycle.fraction(0.0), 0.0);
+    assert!((cycle.fraction(2.0) - 0.5849625).abs() < 1e-6);
+
+    let fraction = cycle.fraction(1023.0);
+
+    assert!((fraction - fraction.round()).abs() < 1e-9);
+}
+
+/// How many of an image's points escaped at each iteration count, for
+/// spreading the palette evenly over the points instead of over the counts
+/// (histogram equalization)
+///
+/// Most points of a view escape within a narrow band of iteration counts, so
+/// stretching the palette over all the counts up to the limit leaves most of
+/// its colors to the few points outside that band.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// The iteration limit, where points didn't escape
+    limit: u32,
+    /// The iteration counts escaped points escaped at, in increasing order,
+    /// without the counts no point escaped at, so that high limits don't
+    /// take room for every count up to them
+    counts: Vec<u32>,
+    /// For each of `counts`, and then past the last one, the fraction of
+    /// escaped points that escaped in fewer iterations than that count
+    cdf: Vec<f64>,
+}
+
+impl Histogram {
+    /// Counts up the iteration counts of an image's points, where `limit`
+    /// means a point didn't escape, so it isn't counted
+    pub fn new<I: IntoIterator<Item = u32>>(iterations: I, limit: u32) -> Histogram {
+        let mut escaped: Vec<u32> = iterations.into_iter().filter(|&n| n < limit).collect();
+
+        escaped.sort_unstable();
+
+        let mut counts = Vec::new();
+        let mut cdf = Vec::new();
+
+        for (i, &n) in escaped.iter().enumerate() {
+            if counts.last() != Some(&n) {
+                counts.push(n);
+                cdf.push(i as f64 / escaped.len() as f64);
+            }
+        }
+
+        cdf.push(if escaped.is_empty() { 0.0 } else { 1.0 });
+
+        return Histogram { limit, counts, cdf };
+    }
+
+    /// Returns the fraction of escaped points that escaped in fewer than `n`
+    /// iterations
+    fn escaped_sooner(&self, n: u32) -> f64 {
+        return self.cdf[self.counts.partition_point(|&count| count < n)];
+    }
+
+    /// Returns how far along the palette, from 0 to 1, `iterations` lands,
+    /// which is the fraction of escaped points that escaped sooner, with
+    /// fractional counts landing between the integer ones around them
+    pub fn fraction(&self, iterations: f64) -> f64 {
+        let limit = self.limit.max(1);
+        let iterations = iterations.max(0.0).min(limit as f64);
+        let n = (iterations.floor() as u32).min(limit - 1);
+        let (below, above) = (self.escaped_sooner(n), self.escaped_sooner(n + 1));
+
+        return below + (above - below) * (iterations - n as f64);
+    }
+}
+
+#[test]
+fn test_histogram() {
+    // Half the escaped points took 2 iterations, and the points at the limit
+    // don't count
+    let histogram = Histogram::new(vec![0, 2, 2, 3, 10, 10, 10], 10);
+
+    assert_eq!(histogram.fraction(0.0), 0.0);
+    assert_eq!(histogram.fraction(1.0), 0.25);
+    assert_eq!(histogram.fraction(2.0), 0.25);
+    assert_eq!(histogram.fraction(2.5), 0.5);
+    assert_eq!(histogram.fraction(3.0), 0.75);
+    assert_eq!(histogram.fraction(9.0), 1.0);
+    assert_eq!(histogram.fraction(10.0), 1.0);
+
+    // Nothing escaping is nothing to spread the palette over
+    assert_eq!(Histogram::new(vec![5, 5], 5).fraction(3.0), 0.0);
+
+    // Huge limits only take room for the counts points escaped at
+    let histogram = Histogram::new(vec![1, 3, 4_000_000_000], 4_000_000_000);
+
+    assert_eq!(histogram.counts, vec![1, 3]);
+    assert_eq!(histogram.fraction(2.0), 0.5);
+    assert_eq!(histogram.fraction(3_999_999_999.0), 1.0);
+}
+
+/// How the points that didn't escape (the inside of the set) are colored
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interior {
+    /// All in the inside color
+    Flat,
+    /// Along the palette by |z| at the last iteration, from its first color at
+    /// 0 to its last at 2
+    Magnitude,
+    /// In the palette's colors, one for each period of the cycle the orbit
+    /// settles into, so that each bulb of the Mandelbrot set gets its own, or
+    /// in the inside color where no cycle shows up by the last iteration
+    Period,
+    /// Around the palette by the average angle of the orbit's `z`s
+    Angle,
+}
+
+impl Interior {
+    /// Returns whether points have to be iterated again, to follow their
+    /// orbit, to be colored this way
+    pub fn uses_orbit(&self) -> bool {
+        return *self == Interior::Period || *self == Interior::Angle;
+    }
+}
+
+/// Prints the interior the way it follows `interior:` in an option
+impl std::fmt::Display for Interior {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Interior::Flat => write!(f, "flat"),
+            Interior::Magnitude => write!(f, "magnitude"),
+            Interior::Period => write!(f, "period"),
+            Interior::Angle => write!(f, "angle"),
+        }
+    }
+}
+
+/// How escape results are turned into colors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coloring {
+    pub palette: Palette,
+    /// Whether to color by the fractional iteration count instead of the
+    /// integer one, which removes the banding between escape times
+    pub smooth: bool,
+    /// How often to repeat the palette, or `None` to stretch it once over the
+    /// iterations up to the limit
+    pub cycle: Option<Cycle>,
+    /// Whether to spread the palette over the image's points by their
+    /// `histogram`, which takes precedence over `cycle`
+    pub equalize: bool,
+    /// The histogram of the image being rendered, which has to be counted
+    /// before rendering it (see `count_histogram`) for `equalize` to do
+    /// anything
+    pub histogram: Option<Histogram>,
+    /// The color of the points that didn't escape, or of those that `interior`
+    /// can't tell apart
+    pub inside: Rgb<u8>,
+    /// How to color the points that didn't escape
+    pub interior: Interior,
+}
+
+impl Coloring {
+    /// Creates a banded (not smooth) coloring with `palette`, stretched once
+    /// over the iterations up to the limit, and a black inside
+    pub fn new(palette: Palette) -> Coloring {
+        return Coloring {
+            palette,
+            smooth: false,
+            cycle: None,
+            equalize: false,
+            histogram: None,
+            inside: BLACK,
+            interior: Interior::Flat
+        };
+    }
+
+    /// Returns the color of a point that escaped as `escape` describes
+    ///
+    /// Points that didn't escape get `interior_color` without an orbit, so
+    /// they need to be colored by `interior_color` instead when `interior`
+    /// uses one.
+    pub fn color(&self, escape: &Escape, limit: u32) -> Rgb<u8> {
+        // Whether a point escaped goes by its whole iteration count, since a
+        // smooth count can round up to the limit
+        if !escape.escaped(limit) {
+            return self.interior_color(escape, &Orbit::new());
+        }
+
+        let iterations = if self.smooth {
+            escape.smooth_iterations(limit)
+        }
+        else {
+            escape.iterations as f64
+        };
+
+        let histogram = self.histogram.as_ref().filter(|_| self.equalize);
+
+        if let Some(histogram) = histogram {
+            return self.palette.color_at(histogram.fraction(iterations));
+        }
+
+        if let Some(cycle) = &self.cycle {
+            return self.palette.cyclic_color(cycle.fraction(iterations));
+        }
+
+        if self.palette.positions.is_some() {
+            return self.palette.color_at(iterations / limit as f64);
+        }
+
+        if self.smooth {
+            return smooth_iterations_to_color_with(
+                iterations,
+                limit,
+                &self.palette.colors,
+                self.palette.interpolation
+            );
+        }
+
+        return iterations_to_color_with(
+            escape.iterations,
+            limit,
+            &self.palette.colors,
+            self.palette.interpolation
+        );
+    }
+
+    /// Returns the color of a point that didn't escape, as `escape` describes,
+    /// after going through `orbit`
+    pub fn interior_color(&self, escape: &Escape, orbit: &Orbit) -> Rgb<u8> {
+        match self.interior {
+            Interior::Flat => return self.inside,
+            Interior::Magnitude => return self.palette.color_at(escape.norm_sqr.sqrt() / 2.0),
+            Interior::Period => {
+                return match orbit.period {
+                    Some(period) => self.palette.colors[(period as usize - 1) % self.palette.colors.len()],
+                    None => self.inside
+                };
+            }
+            Interior::Angle => {
+                return match orbit.average_angle() {
+                    Some(angle) => {
+                        let fraction = (angle + std::f64::consts::PI) / std::f64::consts::TAU;
+
+                        self.palette.cyclic_color(fraction)
+                    }
+                    None => self.inside
+                };
+            }
+        }
+    }
+}
+
+#[test]
+fn test_interior_color() {
+    use num_complex::Complex;
+
+    let mut coloring = Coloring {
+        inside: WHITE,
+        ..Coloring::new(Palette::new(vec![RED, GREEN, BLUE]))
+    };
+    let inside = Escape { iterations: 100, norm_sqr: 1.0 };
+    let orbit_of = |zs: &[Complex<f64>]| {
+        let mut orbit = Orbit::new();
+
+        for &z in zs {
+            orbit.visit(z);
+        }
+
+        return orbit;
+    };
+    let up = Complex { re: 0.0, im: 1.0 };
+    let down = Complex { re: 0.0, im: -1.0 };
+
+    // The inside color stands in for black
+    assert_eq!(coloring.color(&inside, 100), WHITE);
+    assert_eq!(coloring.interior_color(&inside, &orbit_of(&[up, down])), WHITE);
+
+    coloring.interior = Interior::Magnitude;
+
+    assert_eq!(coloring.color(&inside, 100), GREEN);
+
+    coloring.interior = Interior::Period;
+
+    assert_eq!(coloring.interior_color(&inside, &orbit_of(&[up, down, up, down])), GREEN);
+    assert_eq!(coloring.interior_color(&inside, &orbit_of(&[up, down])), WHITE);
+
+    // Just above and just below -1 average out to pointing left, all the way
+    // around the palette, rather than to pointing right, halfway around it
+    coloring.interior = Interior::Angle;
+
+    let left = orbit_of(&[Complex { re: -1.0, im: 0.1 }, Complex { re: -1.0, im: -0.1 }]);
+
+    assert_eq!(coloring.interior_color(&inside, &left), RED);
+    assert_eq!(coloring.interior_color(&inside, &Orbit::new()), WHITE);
+
+    // A point that escaped on the last iteration is outside, however close
+    // its smooth count comes to the limit
+    coloring.smooth = true;
+
+    let last_moment = Escape { iterations: 99, norm_sqr: 4.0 + 1e-14 };
+
+    assert_ne!(coloring.color(&last_moment, 100), WHITE);
+}
+
+#[test]
+fn test_cyclic_coloring() {
+    let coloring = Coloring {
+        cycle: Some(Cycle { period: 30.0, log: false, offset: 0.0 }),
+        ..Coloring::new(Palette::new(vec![RED, GREEN, BLUE]))
+    };
+    let escape = |iterations| Escape { iterations, norm_sqr: 4.0 };
+
+    // The palette comes around again and again, however high the limit
+    assert_eq!(coloring.color(&escape(10), 100_000), GREEN);
+    assert_eq!(coloring.color(&escape(40), 100_000), GREEN);
+    assert_eq!(coloring.color(&escape(99_990), 100_000), RED);
+    assert_eq!(coloring.color(&escape(100_000), 100_000), BLACK);
+}
+
+#[test]
+fn test_equalized_coloring() {
+    let escape = |iterations| Escape { iterations, norm_sqr: 4.0 };
+    let mut coloring = Coloring {
+        equalize: true,
+        ..Coloring::new(Palette::new(vec![RED, GREEN, BLUE]))
+    };
+
+    // Without a histogram counted yet, the palette is stretched as usual
+    assert_eq!(coloring.color(&escape(50), 100), GREEN);
+
+    // With most points escaping at 1 or 2 iterations, those two counts get
+    // most of the palette
+    coloring.histogram = Some(Histogram::new(vec![1, 1, 1, 2, 2, 2, 90, 100], 100));
+
+    assert_eq!(coloring.color(&escape(1), 100), RED);
+    assert_eq!(coloring.color(&escape(2), 100), blend_colors(&RED, &GREEN, 6.0 / 7.0));
+    assert_eq!(coloring.color(&escape(90), 100), blend_colors(&GREEN, &BLUE, 5.0 / 7.0));
+    assert_eq!(coloring.color(&escape(100), 100), BLACK);
 }
 
 /// Writes a generated test gradient to file for visually inspecting color
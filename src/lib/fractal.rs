@@ -0,0 +1,236 @@
+use num_complex::Complex;
+
+/// What happened to a point of the complex plane when it was iterated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Escape {
+    /// The number of iterations it took for the point to escape
+    ///
+    /// If this is the iteration limit, the point did not escape, indicating
+    /// that it's probably in the set.
+    pub iterations: u32,
+    /// The squared magnitude of `z` when iteration stopped
+    ///
+    /// For escaped points, this is the first |z|² past the escape radius, which
+    /// is what `smooth_iterations` needs to tell how far past it `z` landed.
+    pub norm_sqr: f64,
+}
+
+impl Escape {
+    /// Returns whether the point escaped before reaching `limit` iterations
+    pub fn escaped(&self, limit: u32) -> bool {
+        return self.iterations < limit;
+    }
+
+    /// Returns the iteration count as a continuous, fractional number, up to
+    /// the given `limit`
+    ///
+    /// Integer iteration counts make the colors of neighboring escape times
+    /// jump from one to the next, showing up as bands. This "normalized
+    /// iteration count" smooths the jump by also considering how far past the
+    /// escape radius of 2 `z` landed: just past it, the count is about
+    /// `iterations + 1`, and at |z| = 4 (where `z` barely missed escaping one
+    /// iteration sooner), it's about `iterations`.
+    ///
+    /// Points that didn't escape return `limit`, and points that did always
+    /// return less, so the two can't be mixed up.
+    pub fn smooth_iterations(&self, limit: u32) -> f64 {
+        if !self.escaped(limit) {
+            return limit as f64;
+        }
+
+        // log2|z| == log2(|z|²) / 2
+        let log2_norm = self.norm_sqr.log2() / 2.0;
+        let smooth = self.iterations as f64 + 1.0 - log2_norm.log2();
+
+        // Just below the limit, since the iteration before it plus a hair can
+        // round up to the limit itself
+        return smooth.max(0.0).min(limit as f64 - f64::EPSILON * limit as f64);
+    }
+}
+
+#[test]
+fn test_smooth_iterations() {
+    // Just past the escape radius
+    let barely = Escape { iterations: 5, norm_sqr: 4.0 + 1e-9 };
+    assert!((barely.smooth_iterations(100) - 6.0).abs() < 1e-6);
+
+    // |z| == 4, which is as far as z * z can take z from just inside the radius
+    let far = Escape { iterations: 5, norm_sqr: 16.0 };
+    assert!((far.smooth_iterations(100) - 5.0).abs() < 1e-6);
+
+    // In between
+    let between = Escape { iterations: 5, norm_sqr: 9.0 };
+    assert!(between.smooth_iterations(100) > 5.0);
+    assert!(between.smooth_iterations(100) < 6.0);
+
+    // Didn't escape
+    let bounded = Escape { iterations: 100, norm_sqr: 0.25 };
+    assert_eq!(bounded.smooth_iterations(100), 100.0);
+
+    // Escaping on the last iteration, just past the escape radius, still comes
+    // out below the limit
+    let clamped = Escape { iterations: 99, norm_sqr: 4.0 + 1e-14 };
+    assert!(clamped.smooth_iterations(100) < 100.0);
+}
+
+/// A fractal whose points can be tested for membership by iteration
+///
+/// This is what the render strategies use to turn a point of the complex
+/// plane into something colorable, so implementing it for another formula is
+/// all it takes to render that formula with any of them.
+pub trait Fractal {
+    /// Iterates `point`, up to the given `limit`, and returns how it escaped
+    fn escape(&self, point: Complex<f64>, limit: u32) -> Escape;
+
+    /// Iterates `point` like `escape`, and also returns what the `z`s it went
+    /// through had in common, for coloring the points that don't escape by
+    /// where they go instead
+    ///
+    /// Fractals that don't say return an empty orbit, which leaves their
+    /// insides the plain inside color.
+    fn orbit(&self, point: Complex<f64>, limit: u32) -> (Escape, Orbit) {
+        return (self.escape(point, limit), Orbit::new());
+    }
+}
+
+/// How close two `z`s of an orbit have to be, squared, to be the same point of
+/// a cycle
+const PERIOD_TOLERANCE: f64 = 1e-12;
+
+/// What the `z`s a point went through while iterating had in common, gathered
+/// one `z` at a time so the orbit itself never has to be kept
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orbit {
+    /// The number of `z`s visited
+    pub length: u32,
+    /// The period of the cycle the orbit settled into, once one shows up
+    pub period: Option<u32>,
+    /// The sums of the sines and cosines of the `z`s' angles, which are the
+    /// parts of the `z`s scaled to unit length
+    sin_sum: f64,
+    cos_sum: f64,
+    /// The `z` that later ones are compared to for finding `period`, and how
+    /// many `z`s ago it was saved, and how many it's kept for
+    saved: Option<Complex<f64>>,
+    since_saved: u32,
+    saved_for: u32,
+}
+
+impl Orbit {
+    /// Creates an orbit that hasn't visited any `z`s yet
+    pub fn new() -> Orbit {
+        return Orbit {
+            length: 0,
+            period: None,
+            sin_sum: 0.0,
+            cos_sum: 0.0,
+            saved: None,
+            since_saved: 0,
+            saved_for: 1,
+        };
+    }
+
+    /// Adds the next `z` of the orbit
+    pub fn visit(&mut self, z: Complex<f64>) {
+        let norm = z.norm();
+
+        self.length += 1;
+
+        // 0 has no angle, so it doesn't pull the average any way
+        if norm > 0.0 {
+            self.sin_sum += z.im / norm;
+            self.cos_sum += z.re / norm;
+        }
+
+        if self.period.is_some() {
+            return;
+        }
+
+        // Brent's cycle detection: wait for `z` to come back around to the
+        // saved one, saving a new one for twice as long each time it doesn't,
+        // until the saved one is in the cycle and kept for long enough to see
+        // all of it
+        if let Some(saved) = self.saved {
+            self.since_saved += 1;
+
+            if (z - saved).norm_sqr() < PERIOD_TOLERANCE {
+                self.period = Some(self.since_saved);
+                return;
+            }
+
+            if self.since_saved == self.saved_for {
+                self.saved_for = self.saved_for.saturating_mul(2);
+            }
+            else {
+                return;
+            }
+        }
+
+        self.saved = Some(z);
+        self.since_saved = 0;
+    }
+
+    /// Returns the average angle of the orbit's `z`s, between -π and π, or
+    /// `None` if it has no `z`s
+    ///
+    /// Angles are averaged around the circle, so that ones just either side of
+    /// ±π average out to π instead of 0.
+    pub fn average_angle(&self) -> Option<f64> {
+        if self.length == 0 {
+            return None;
+        }
+
+        return Some(self.sin_sum.atan2(self.cos_sum));
+    }
+}
+
+impl Default for Orbit {
+    fn default() -> Orbit {
+        return Orbit::new();
+    }
+}
+
+#[test]
+fn test_orbit_period() {
+    let orbit_of = |zs: &[Complex<f64>]| {
+        let mut orbit = Orbit::new();
+
+        for &z in zs {
+            orbit.visit(z);
+        }
+
+        return orbit;
+    };
+    let a = Complex { re: 0.5, im: 0.0 };
+    let b = Complex { re: -0.5, im: 0.25 };
+    let c = Complex { re: 0.0, im: -1.0 };
+
+    assert_eq!(orbit_of(&[a, a]).period, Some(1));
+    assert_eq!(orbit_of(&[c, a, b, a, b]).period, Some(2));
+    assert_eq!(orbit_of(&[a, b, c, a, b, c, a]).period, Some(3));
+    assert_eq!(orbit_of(&[a, b, c]).period, None);
+    assert_eq!(orbit_of(&[]).period, None);
+
+    // Once found, the period stays put
+    assert_eq!(orbit_of(&[a, a, b, c]).period, Some(1));
+    assert_eq!(orbit_of(&[a, b, c, a, b, c, a]).length, 7);
+}
+
+#[test]
+fn test_orbit_average_angle() {
+    let mut orbit = Orbit::new();
+
+    assert_eq!(orbit.average_angle(), None);
+
+    // Just above and just below -1 average out to -1, not 1
+    orbit.visit(Complex { re: -1.0, im: 0.1 });
+    orbit.visit(Complex { re: -1.0, im: -0.1 });
+
+    assert!((orbit.average_angle().unwrap().abs() - std::f64::consts::PI).abs() < 1e-9);
+
+    // 0 counts toward the length, but not the angle
+    orbit.visit(Complex { re: 0.0, im: 0.0 });
+
+    assert_eq!(orbit.length, 3);
+    assert!((orbit.average_angle().unwrap().abs() - std::f64::consts::PI).abs() < 1e-9);
+}
@@ -3,16 +3,37 @@ This file contains code from Programming Rust by Jim Blandy and Jason Orendorff
 (O'Reilly), copyright 2018 Jim Blandy and Jason Orendorff, 978-1-491-92728-1.
 */
 
-use std::io::Write;
-use std::sync::{Arc, Mutex};
+#![allow(clippy::needless_return)]
+
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
 
 use num_complex::Complex;
 use image::{Rgb, RgbImage};
 
-mod threadpool;
-mod mandelbrot;
+pub mod threadpool;
+pub mod fractal;
+pub mod mandelbrot;
 pub mod colors;
+pub mod antialiasing;
+pub mod tiles;
+pub mod bench;
+pub mod viewport;
+pub mod scene_file;
+pub mod metadata;
+pub mod palette_file;
+
+pub use fractal::{Escape, Fractal};
+use colors::{Coloring, Cycle, Histogram, Interior, Interpolation, Palette};
+use antialiasing::{EdgeThreshold, Supersampling};
+use threadpool::ThreadPool;
+use mandelbrot::FractalKind;
+use tiles::TileOrder;
+use bench::{Bench, ReportFormat};
+use viewport::Viewport;
+use scene_file::SceneFile;
+use palette_file::{find_palette_files, PALETTE_DIR};
 
 /// Parsed/validated arguments
 pub struct Args {
@@ -20,42 +41,62 @@ pub struct Args {
     pub threads: u32,
     pub image_width: usize,
     pub image_height: usize,
-    pub complex_upper_left_corner: Complex<f64>,
-    pub complex_lower_right_corner: Complex<f64>,
+    pub viewport: Viewport,
     pub output_filename: String,
-    pub color_theme: Vec<Rgb<u8>>,
+    pub coloring: Coloring,
+    pub fractal: FractalKind,
+    pub supersampling: Supersampling,
+    pub adaptive_antialiasing: Option<Supersampling>,
+    pub strategy: RenderStrategy,
+    /// The name of the color theme `coloring` uses
+    pub palette_name: String,
+    /// The color space to blend the palette's colors in, if not its own
+    pub interpolation: Option<Interpolation>,
+    /// Where to write the scene these arguments describe, if anywhere
+    pub save_scene: Option<String>,
 }
 
-fn print_usage(exe: &str, color_themes: HashMap<&str, Vec<Rgb<u8>>>) {
-    writeln!(std::io::stderr(),
-        "Usage: mandelbrot <output_filename> <resolution> <upper_left_c> \
-        <lower_right_c> <limit> <threads> <color_theme>\n"
-    ).unwrap();
-    writeln!(std::io::stderr(),
-        "\t- output_filename is the filename of output image\
-        \n\t- resolution defines the dimensions of output image, in pixels\
-        \n\t- upper_left_c is upper left corner of the complex plane to render\
-        \n\t- lower_right_c is lower right corner of the complex plane to render\
-        \n\t- limit is the number of iterations with which to test points (higher \
-        is slower but more accurate)\
-        \n\t- threads is the number of threads to use\
-        \n\t- color_theme is one of:"
-    ).unwrap();
-    // List available color themes
-    for theme_name in color_themes.keys() {
-        writeln!(std::io::stderr(), "\t\t- {}", theme_name).unwrap();
+impl Default for Args {
+    /// Returns the arguments with every flag left out
+    fn default() -> Args {
+        return Args {
+            limit: DEFAULT_ITERATIONS,
+            threads: crate::threadpool::available_threads() as u32,
+            image_width: DEFAULT_SIZE.0,
+            image_height: DEFAULT_SIZE.1,
+            viewport: Viewport::from_center_zoom(DEFAULT_CENTER, DEFAULT_ZOOM),
+            output_filename: DEFAULT_OUTPUT.to_string(),
+            coloring: Coloring::new(color_themes().remove(DEFAULT_PALETTE).unwrap()),
+            fractal: FractalKind::Mandelbrot,
+            supersampling: Supersampling::None,
+            adaptive_antialiasing: None,
+            strategy: RenderStrategy::PooledRows,
+            palette_name: DEFAULT_PALETTE.to_string(),
+            interpolation: None,
+            save_scene: None,
+        };
     }
-    writeln!(std::io::stderr(),
-        "\n\tExample:\n\t{} frac.png 2000x2000 -0.245178,-0.650185 -0.244486,-0.649417 \
-        350 6 k8_peacock",
-        exe
-    ).unwrap();
 }
 
-/// Validates and returns input in an `Args` struct
-pub fn parse_input() -> Args {
-    let got_args: Vec<String> = std::env::args().collect();
-    let color_themes = HashMap::from([
+/// What to do, per the command line
+pub enum Command {
+    /// Render an image and write it to a file
+    Render(Args),
+    /// Render a view with every strategy, and print how long each one took
+    Bench { bench: Bench<FractalKind>, format: ReportFormat },
+}
+
+/// Defaults for the flags that aren't given
+const DEFAULT_OUTPUT: &str = "fractal.png";
+const DEFAULT_SIZE: (usize, usize) = (1000, 1000);
+const DEFAULT_CENTER: Complex<f64> = Complex { re: -0.75, im: 0.0 };
+const DEFAULT_ZOOM: f64 = 1.0;
+const DEFAULT_ITERATIONS: u32 = 250;
+const DEFAULT_PALETTE: &str = "k8_peacock";
+
+/// Returns the built-in color themes, by name
+fn color_themes() -> HashMap<&'static str, Palette> {
+    return HashMap::from([
         ("grayscale",       crate::colors::grayscale()),
         ("space",           crate::colors::space()),
         ("fire",            crate::colors::fire()),
@@ -65,38 +106,872 @@ pub fn parse_input() -> Args {
         ("mojave",          crate::colors::mojave()),
         ("houndeye",        crate::colors::houndeye()),
     ]);
+}
+
+/// Returns the names of the built-in color themes, alphabetically
+fn color_theme_names() -> Vec<&'static str> {
+    let mut names: Vec<&str> = color_themes().keys().copied().collect();
+
+    names.sort_unstable();
+
+    return names;
+}
 
-    if got_args.len() == 8 {
-        let output_filename: &str = &got_args[1];
-        let resolution: (usize, usize) = parse_pair(&got_args[2], 'x')
-            .expect("error parsing image resolution");
-        let complex_upper_left_corner: Complex<f64> = parse_complex(&got_args[3])
-            .expect("error parsing upper left complex bound");
-        let complex_lower_right_corner: Complex<f64> = parse_complex(&got_args[4])
-            .expect("error parsing lower right complex bound");
-        let limit: u32 = got_args[5].parse().unwrap();
-        let threads: u32 = got_args[6].parse().unwrap();
-        let color_theme: &str = &got_args[7];
-
-        if color_themes.contains_key(color_theme) {
-            let ret_args = Args {
-                limit: limit,
-                threads: threads,
-                image_width: resolution.0,
-                image_height: resolution.1,
-                complex_upper_left_corner: complex_upper_left_corner,
-                complex_lower_right_corner: complex_lower_right_corner,
-                output_filename: output_filename.to_string(),
-                color_theme: color_themes.get(color_theme).unwrap().to_vec()
-            };
-    
-            return ret_args;
+fn print_usage(exe: &str) {
+    eprintln!(
+        "Usage: {0} [--flag <value>...] [options...]\
+        \n   or: {0} <output_filename> <resolution> <upper_left_c> \
+        <lower_right_c> <limit> <threads> <color_theme> [options...]\
+        \n   or: {0} bench <resolution> <upper_left_c> <lower_right_c> \
+        <limit> [bench_options...]\n",
+        exe
+    );
+    eprintln!(
+        "\t- flags (which can also be given like `--flag=<value>`) are any of:\
+        \n\t\t--output <filename>, the filename of output image (default: {})\
+        \n\t\t--size <width>x<height>, the dimensions of output image, in pixels \
+        (default: {}x{})\
+        \n\t\t--center <re>,<im>, the point of the complex plane in the middle \
+        of the image (default: {},{})\
+        \n\t\t--zoom <zoom>, how far to zoom in on the center, where 1 fits a \
+        circle of radius 2 around it in the image (default: {})\
+        \n\t\t--radius <radius>, the radius of the circle around the center \
+        to fit in the image, instead of a zoom\
+        \n\t\t--rotation <degrees>, how far to turn the view counterclockwise \
+        around the center (default: 0)\
+        \n\t\t--iterations <limit>, the number of iterations with which to test \
+        points (default: {})\
+        \n\t\t--threads <threads>, the number of threads to use (default: auto)\
+        \n\t\t--palette <color_theme>, the color theme (default: {})\
+        \n\t\t--scene <file>, to render the scene described in a TOML or JSON \
+        file (`.json` files are read as JSON), which the flags and options \
+        after it can change\
+        \n\t\t--save-scene <file>, to write the scene being rendered to a \
+        TOML or JSON file, to render again later with `--scene`\
+        \n\t\t--from-image <file>, to render the scene embedded in a PNG \
+        this program wrote, like `--scene`, to render it again at a higher \
+        resolution, say\
+        \n\t\t--help, to print this",
+        DEFAULT_OUTPUT,
+        DEFAULT_SIZE.0, DEFAULT_SIZE.1,
+        DEFAULT_CENTER.re, DEFAULT_CENTER.im,
+        DEFAULT_ZOOM,
+        DEFAULT_ITERATIONS,
+        DEFAULT_PALETTE
+    );
+    eprintln!(
+        "\t- output_filename is the filename of output image\
+        \n\t- resolution defines the dimensions of output image, in pixels\
+        \n\t- upper_left_c is upper left corner of the complex plane to render\
+        \n\t- lower_right_c is lower right corner of the complex plane to render \
+        (which is widened or heightened, if need be, to match the resolution's \
+        aspect ratio)\
+        \n\t- limit is the number of iterations with which to test points (higher \
+        is slower but more accurate)\
+        \n\t- threads is the number of threads to use, or `auto` for as many as \
+        there are cpus available\
+        \n\t- color_theme is one of these built-in themes:"
+    );
+    // List available color themes
+    for theme_name in color_theme_names() {
+        eprintln!("\t\t- {}", theme_name);
+    }
+    // ...and palette files, checking that each one can be used
+    let palette_files = find_palette_files(Path::new(PALETTE_DIR));
+
+    if !palette_files.is_empty() {
+        eprintln!("\t  or one of these palette files in `{}` (under the current directory):", PALETTE_DIR);
+    }
+    for (name, path) in palette_files {
+        match crate::palette_file::load(&path) {
+            Ok(palette) => eprintln!("\t\t- {} ({} colors)", name, palette.colors.len()),
+            Err(message) => eprintln!("\t\t- {} (unusable: {})", name, message),
         }
     }
+    eprintln!(
+        "\t  or the path of a palette file (or the name of one in `{}`, which \
+        is looked for under the current directory), which has one hex color, \
+        like `#ff8800`, per line, optionally followed by its position, like \
+        `#ff8800 0.25`, or is a GIMP palette (`.gpl`), or is JSON (`.json`) \
+        like `{{\"colors\": [\"#ff8800\", {{\"color\": \"#008080\", \
+        \"position\": 0.25}}, \"#000000\"]}}`, where positions, from 0 to 1, \
+        are optional",
+        PALETTE_DIR
+    );
+    eprintln!(
+        "\t- options are any of:\
+        \n\t\t- mandelbrot, to render the Mandelbrot set (the default)\
+        \n\t\t- julia:<c>, to render the Julia set of the constant c instead, \
+        like `julia:-0.8,0.156`\
+        \n\t\t- smooth, to blend colors by fractional iteration counts, \
+        which removes banding\
+        \n\t\t- ss:<n>[:<pattern>], to average n×n samples per pixel, which \
+        smooths jagged edges and thin filaments (slower by a factor of n×n), \
+        where n is up to 16 and pattern is `grid` (the default), `jittered` or \
+        `rotated`\
+        \n\t\t- aa:<n>[:<pattern>], like ss, but only for pixels on the edges \
+        between colors or escape counts, which is almost as good for much less \
+        time\
+        \n\t\t- strategy:<strategy>, to split up the work between threads \
+        another way, where strategy is `singlethreaded`, `segments`, `rows` \
+        (the default), `pixels` or `tiles[:<width>x<height>][:<order>]`, and \
+        order is `row-major`, `spiral` (the default) or `hilbert`\
+        \n\t\t- interpolation:<space>, to blend the palette's colors in another \
+        color space than its own (which is `srgb` for the built-in themes), \
+        where space is `srgb`, `linear` (linear RGB), `hsv`, `hsl` or `oklab`, \
+        which looks the most even\
+        \n\t\t- cycle:<k>[:<offset>], to repeat the palette every k \
+        iterations instead of stretching it once over all of them, which keeps \
+        deep zooms colorful, starting offset (from 0 to 1) of the way around \
+        it, or cycle:log:<k>[:<offset>] to repeat it every time the log of \
+        the iteration count grows by k\
+        \n\t\t- histogram, to spread the palette evenly over the pixels \
+        instead of over the iteration counts, by counting how many pixels \
+        escape at each one first (which takes a second pass)\
+        \n\t\t- inside:<color>, to color the inside of the set (the points \
+        that don't escape) another color than black, like `inside:#102030`\
+        \n\t\t- interior:<mode>, to color the inside of the set with the \
+        palette, where mode is `flat` (all the inside color, the default), \
+        `magnitude` (by |z| at the last iteration), `period` (by the period of \
+        the cycle each point's orbit settles into) or `angle` (by the average \
+        angle of each point's orbit)\
+        \n\t- bench_options are any of mandelbrot, julia:<c>, smooth and \
+        ss:<n>[:<pattern>], as above, or:\
+        \n\t\t- threads:<n>[,<n>...], to benchmark with each of these thread \
+        counts (the default is 1, 2, 4, ... up to as many as there are cpus \
+        available)\
+        \n\t\t- runs:<n>, to render with each strategy and thread count n \
+        times (the default is 3) and report the median\
+        \n\t\t- format:<format>, to print the results as a `table` (the \
+        default), `csv` or `json`"
+    );
+    eprintln!(
+        "\n\tExample:\n\t{} --output frac.png --size 2000x2000 \
+        --center -0.244832,-0.649801 --zoom 5208 --iterations 350 --palette k8_peacock",
+        exe
+    );
+    eprintln!(
+        "\n\tSame example, positionally:\n\t{} frac.png 2000x2000 \
+        -0.245178,-0.650185 -0.244486,-0.649417 350 6 k8_peacock",
+        exe
+    );
+    eprintln!(
+        "\n\tJulia example:\n\t{} julia.png 2000x1000 -2.0,1.0 2.0,-1.0 \
+        350 6 fire julia:-0.8,0.156 smooth ss:3",
+        exe
+    );
+    eprintln!(
+        "\n\tScene file example:\n\t{} --scene frac.toml --size 4000x4000 \
+        --output frac_big.png",
+        exe
+    );
+    eprintln!(
+        "\n\tRe-rendering example:\n\t{} --from-image frac.png --size 8000x8000 \
+        --output frac_huge.png",
+        exe
+    );
+    eprintln!(
+        "\n\tBenchmark example:\n\t{} bench 1000x1000 -0.245178,-0.650185 \
+        -0.244486,-0.649417 350 threads:1,2,4 runs:5 format:csv",
+        exe
+    );
+}
+
+/// Validates and returns input as the `Command` to carry out
+///
+/// Prints usage and exits if asked to with `--help` (or no input at all), and
+/// prints what's wrong and exits if the input is invalid.
+pub fn parse_input() -> Command {
+    let got_args: Vec<String> = std::env::args().collect();
+    let args = &got_args[1..];
+
+    if args.is_empty() {
+        print_usage(&got_args[0]);
+        std::process::exit(1);
+    }
+
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_usage(&got_args[0]);
+        std::process::exit(0);
+    }
+
+    match parse_args(args) {
+        Ok(command) => return command,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            eprintln!("(run `{} --help` for usage)", got_args[0]);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Validates `args` (all but the executable's name), which are in one of
+/// three forms: flags, like `--size 800x600`, seven positional arguments, or
+/// `bench` and its positional arguments
+///
+/// Returns the `Command` to carry out, or a message saying what's wrong.
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    if args[0] == "bench" {
+        return parse_bench_args(&args[1..]);
+    }
+
+    if args[0].starts_with("--") {
+        return parse_flag_args(args).map(Command::Render);
+    }
+
+    return parse_positional_args(args).map(Command::Render);
+}
+
+/// Returns `parsed`, or an error message saying that `value` isn't a valid
+/// `name` and what was `expected` instead, if it's `None`
+fn require<T>(parsed: Option<T>, name: &str, value: &str, expected: &str) -> Result<T, String> {
+    return parsed.ok_or_else(|| format!("invalid {} `{}`: expected {}", name, value, expected));
+}
+
+/// Parses an iteration limit, which is a positive number
+fn parse_limit(s: &str) -> Result<u32, String> {
+    let limit = s.parse().ok().filter(|&limit| limit > 0);
+
+    return require(limit, "iteration limit", s, "a positive whole number, like 250");
+}
+
+/// Parses an image resolution, like `"800x600"`, whose width and height are
+/// both positive
+///
+/// `name` is what the resolution is called in the error message.
+fn parse_resolution(s: &str, name: &str) -> Result<(u32, u32), String> {
+    let resolution = parse_pair(s, 'x').filter(|&(width, height)| width > 0 && height > 0);
+
+    return require(resolution, name, s, "<width>x<height>, like 800x600");
+}
+
+/// Parses a color theme, which is the name of a built-in one or of a palette
+/// file in `PALETTE_DIR`, or the path of a palette file
+fn parse_palette(s: &str) -> Result<Palette, String> {
+    if let Some(palette) = color_themes().remove(s) {
+        return Ok(palette);
+    }
 
-    print_usage(&got_args[0], color_themes);
+    let palette_files = find_palette_files(Path::new(PALETTE_DIR));
+
+    if let Some((_, path)) = palette_files.iter().find(|(name, _)| name == s) {
+        return crate::palette_file::load(path);
+    }
+
+    if Path::new(s).is_file() {
+        return crate::palette_file::load(Path::new(s));
+    }
 
-    std::process::exit(1);
+    let names: Vec<&str> = color_theme_names().into_iter()
+        .chain(palette_files.iter().map(|(name, _)| name.as_str()))
+        .collect();
+
+    return Err(format!(
+        "unknown color theme `{}`: expected one of {}, or the path of a palette file",
+        s,
+        names.join(", ")
+    ));
+}
+
+#[test]
+fn test_parse_palette() {
+    assert_eq!(parse_palette("usa"), Ok(crate::colors::usa()));
+
+    // Palette files can be given by path...
+    let path = std::env::temp_dir().join("threadpool_fractal_test_palette.hex");
+
+    std::fs::write(&path, "#000000\n#ffffff\n").unwrap();
+
+    let palette = parse_palette(path.to_str().unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(palette, Ok(Palette::new(vec![Rgb([0, 0, 0]), Rgb([255, 255, 255])])));
+
+    // ...or by name, if they're in the palette directory
+    for (name, path) in find_palette_files(Path::new(PALETTE_DIR)) {
+        assert_eq!(parse_palette(&name), crate::palette_file::load(&path));
+    }
+
+    assert!(parse_palette("plaid").unwrap_err().ends_with(", or the path of a palette file"));
+}
+
+/// Parses `option`, one of the options that can follow the other arguments,
+/// like `smooth` or `ss:3`, into `args`
+fn parse_option(args: &mut Args, option: &str) -> Result<(), String> {
+    if option == "smooth" {
+        args.coloring.smooth = true;
+    }
+    else if option == "histogram" {
+        args.coloring.equalize = true;
+    }
+    else if let Some(pattern) = option.strip_prefix("ss:") {
+        args.supersampling = require(
+            parse_sampling_pattern(pattern), "supersampling", pattern, SAMPLING_FORMAT
+        )?;
+    }
+    else if let Some(pattern) = option.strip_prefix("aa:") {
+        args.adaptive_antialiasing = Some(require(
+            parse_sampling_pattern(pattern), "adaptive anti-aliasing", pattern, SAMPLING_FORMAT
+        )?);
+    }
+    else if option == "mandelbrot" || option.starts_with("julia:") {
+        args.fractal = require(parse_fractal(option), "fractal", option, FRACTAL_FORMAT)?;
+    }
+    else if let Some(strategy) = option.strip_prefix("strategy:") {
+        args.strategy = require(parse_strategy(option), "strategy", strategy, STRATEGY_NAMES)?;
+    }
+    else if let Some(interpolation) = option.strip_prefix("interpolation:") {
+        args.interpolation = Some(require(
+            parse_interpolation(option), "interpolation", interpolation, INTERPOLATION_NAMES
+        )?);
+    }
+    else if let Some(cycle) = option.strip_prefix("cycle:") {
+        args.coloring.cycle = Some(require(parse_cycle(option), "cycle", cycle, CYCLE_FORMAT)?);
+    }
+    else if let Some(inside) = option.strip_prefix("inside:") {
+        args.coloring.inside = require(parse_inside(option), "inside color", inside, INSIDE_FORMAT)?;
+    }
+    else if let Some(interior) = option.strip_prefix("interior:") {
+        args.coloring.interior = require(parse_interior(option), "interior", interior, INTERIOR_NAMES)?;
+    }
+    else {
+        return Err(format!("unknown option `{}`", option));
+    }
+
+    return Ok(());
+}
+
+/// Validates the original form of input: seven positional arguments, then any
+/// options
+fn parse_positional_args(args: &[String]) -> Result<Args, String> {
+    if args.len() < 7 {
+        return Err(format!(
+            "expected 7 arguments (or flags, like `--size`), but got {}",
+            args.len()
+        ));
+    }
+
+    let (image_width, image_height) = parse_resolution(&args[1], "image resolution")?;
+    let complex_upper_left_corner = require(
+        parse_complex(&args[2]), "upper left complex bound", &args[2], "<re>,<im>, like -2.0,1.0"
+    )?;
+    let complex_lower_right_corner = require(
+        parse_complex(&args[3]), "lower right complex bound", &args[3], "<re>,<im>, like 1.0,-1.0"
+    )?;
+    let mut parsed = Args {
+        limit: parse_limit(&args[4])?,
+        threads: require(
+            parse_threads(&args[5]), "thread count", &args[5], "a positive whole number or `auto`"
+        )?,
+        image_width: image_width as usize,
+        image_height: image_height as usize,
+        viewport: Viewport::try_from_corners(complex_upper_left_corner, complex_lower_right_corner)?,
+        output_filename: args[0].to_string(),
+        coloring: Coloring::new(parse_palette(&args[6])?),
+        palette_name: args[6].to_string(),
+        ..Args::default()
+    };
+
+    for option in &args[7..] {
+        parse_option(&mut parsed, option)?;
+    }
+
+    if let Some(interpolation) = parsed.interpolation {
+        parsed.coloring.palette.interpolation = interpolation;
+    }
+
+    parsed.viewport = parsed.viewport.fit(parsed.image_width as u32, parsed.image_height as u32);
+
+    return Ok(parsed);
+}
+
+/// Validates the flag form of input: flags, like `--size 800x600` or
+/// `--size=800x600`, with a default for each one left out, mixed with options
+///
+/// `--scene` and `--from-image` set everything their file describes, so flags
+/// and options after them change the scene, and ones before them are
+/// overridden by it.
+fn parse_flag_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut source_image = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let flag = match arg.strip_prefix("--") {
+            Some(flag) => flag,
+            None => {
+                parse_option(&mut parsed, arg)?;
+                continue;
+            }
+        };
+        let (flag, value) = match flag.find('=') {
+            Some(index) => (&flag[..index], &flag[index + 1..]),
+            None => match args.next() {
+                Some(value) => (flag, value.as_str()),
+                None => return Err(format!("`--{}` needs a value", flag))
+            }
+        };
+
+        match flag {
+            "output" => parsed.output_filename = value.to_string(),
+            "size" => {
+                let (width, height) = parse_resolution(value, "size")?;
+
+                (parsed.image_width, parsed.image_height) = (width as usize, height as usize);
+            }
+            "center" => {
+                parsed.viewport.center = require(
+                    parse_complex(value), "center", value, "<re>,<im>, like -0.75,0.1"
+                )?;
+            }
+            "zoom" => {
+                let zoom: f64 = require(
+                    value.parse().ok().filter(|&zoom: &f64| zoom > 0.0 && zoom.is_finite()),
+                    "zoom", value, "a positive number, like 1 or 2.5e6"
+                )?;
+
+                parsed.viewport.half_width = Viewport::UNZOOMED_RADIUS / zoom;
+                parsed.viewport.half_height = Viewport::UNZOOMED_RADIUS / zoom;
+            }
+            "radius" => {
+                let radius = require(
+                    value.parse().ok().filter(|&radius: &f64| radius > 0.0 && radius.is_finite()),
+                    "radius", value, "a positive number, like 2 or 1e-5"
+                )?;
+
+                parsed.viewport.half_width = radius;
+                parsed.viewport.half_height = radius;
+            }
+            "rotation" => {
+                let degrees: f64 = require(
+                    value.parse().ok().filter(|&degrees: &f64| degrees.is_finite()),
+                    "rotation", value, "a number of degrees, like 45 or -12.5"
+                )?;
+
+                parsed.viewport.rotation = degrees.to_radians();
+            }
+            "iterations" => parsed.limit = parse_limit(value)?,
+            "threads" => {
+                parsed.threads = require(
+                    parse_threads(value), "thread count", value, "a positive whole number or `auto`"
+                )?;
+            }
+            "palette" => {
+                parsed.coloring.palette = parse_palette(value)?;
+                parsed.palette_name = value.to_string();
+            }
+            "scene" => SceneFile::load(value)?.apply_to(&mut parsed)?,
+            "from-image" => {
+                crate::metadata::read_scene(value)?.apply_to(&mut parsed)?;
+                source_image = Some(value);
+            }
+            "save-scene" => parsed.save_scene = Some(value.to_string()),
+            _ => return Err(format!("unknown flag `--{}`", flag))
+        }
+    }
+
+    // The image's scene names the image itself as the output, though maybe by
+    // another path
+    if source_image.is_some_and(|source| is_same_file(source, &parsed.output_filename)) {
+        return Err(format!(
+            "re-rendering `{}` would overwrite it: pick another `--output`",
+            parsed.output_filename
+        ));
+    }
+
+    if let Some(interpolation) = parsed.interpolation {
+        parsed.coloring.palette.interpolation = interpolation;
+    }
+
+    parsed.viewport = parsed.viewport.fit(parsed.image_width as u32, parsed.image_height as u32);
+
+    return Ok(parsed);
+}
+
+/// Returns whether the paths `a` and `b` both lead to the same existing file,
+/// like `frac.png` and `./frac.png` do
+fn is_same_file(a: &str, b: &str) -> bool {
+    return match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false
+    };
+}
+
+/// Turns the strings in `args` into `String`s, like the real command line
+#[cfg(test)]
+fn to_args(args: &[&str]) -> Vec<String> {
+    return args.iter().map(|arg| arg.to_string()).collect();
+}
+
+#[test]
+fn test_parse_flag_args() {
+    // Everything left out has a default
+    let args = parse_flag_args(&to_args(&["--output", "out.png"])).unwrap();
+
+    assert_eq!(args.output_filename, "out.png");
+    assert_eq!((args.image_width, args.image_height), DEFAULT_SIZE);
+    assert_eq!(args.limit, DEFAULT_ITERATIONS);
+    assert_eq!(args.threads, crate::threadpool::available_threads() as u32);
+    assert_eq!(args.coloring.palette, crate::colors::k8_peacock());
+    assert_eq!(args.strategy, RenderStrategy::PooledRows);
+
+    // Flags go with either a space or `=`, in any order, and mix with options
+    let args = parse_flag_args(&to_args(&[
+        "--size=400x200",
+        "smooth",
+        "--center", "1,-1",
+        "--zoom=2",
+        "--iterations", "99",
+        "--threads", "3",
+        "--palette=fire",
+        "julia:-0.8,0.156",
+        "interpolation:hsl",
+        "cycle:log:0.5",
+        "histogram",
+        "inside:#102030",
+        "interior:angle",
+    ])).unwrap();
+
+    assert_eq!(args.output_filename, DEFAULT_OUTPUT);
+    assert_eq!((args.image_width, args.image_height), (400, 200));
+    assert_eq!(
+        args.viewport.corners(),
+        (Complex { re: -1.0, im: 0.0 }, Complex { re: 3.0, im: -2.0 })
+    );
+    assert_eq!(args.limit, 99);
+    assert_eq!(args.threads, 3);
+    assert_eq!(args.coloring.palette.colors, crate::colors::fire().colors);
+    assert!(args.coloring.smooth);
+    assert_eq!(args.coloring.palette.interpolation, Interpolation::Hsl);
+    assert_eq!(args.coloring.cycle, Some(Cycle { period: 0.5, log: true, offset: 0.0 }));
+    assert!(args.coloring.equalize);
+    assert_eq!(args.coloring.inside, Rgb([16, 32, 48]));
+    assert_eq!(args.coloring.interior, Interior::Angle);
+    assert_eq!(args.fractal, FractalKind::Julia(Complex { re: -0.8, im: 0.156 }));
+
+    // A radius can stand in for a zoom
+    let args = parse_flag_args(&to_args(&["--radius", "0.5", "--size", "100x200"])).unwrap();
+
+    assert_eq!(args.viewport.zoom(), 4.0);
+    assert_eq!((args.viewport.half_width, args.viewport.half_height), (0.5, 1.0));
+    assert_eq!(args.viewport.rotation, 0.0);
+
+    // Rotations are given in degrees
+    let args = parse_flag_args(&to_args(&["--rotation=-90"])).unwrap();
+
+    assert_eq!(args.viewport.rotation, -std::f64::consts::FRAC_PI_2);
+
+    // Each bad argument gets its own message
+    let error = |args: &[&str]| parse_flag_args(&to_args(args)).err().unwrap();
+
+    assert_eq!(error(&["--size", "big"]), "invalid size `big`: expected <width>x<height>, like 800x600");
+    assert_eq!(error(&["--size", "0x10"]), "invalid size `0x10`: expected <width>x<height>, like 800x600");
+    assert_eq!(error(&["--size", "10x0"]), "invalid size `10x0`: expected <width>x<height>, like 800x600");
+    assert_eq!(error(&["--zoom", "-1"]), "invalid zoom `-1`: expected a positive number, like 1 or 2.5e6");
+    assert_eq!(error(&["--iterations", "lots"]), "invalid iteration limit `lots`: expected a positive whole number, like 250");
+    assert_eq!(error(&["--threads", "0"]), "invalid thread count `0`: expected a positive whole number or `auto`");
+    assert!(error(&["--palette", "plaid"]).starts_with("unknown color theme `plaid`: expected one of fire, "));
+    assert_eq!(error(&["--center"]), "`--center` needs a value");
+    assert_eq!(error(&["--colour", "red"]), "unknown flag `--colour`");
+    assert_eq!(error(&["--zoom", "2", "shiny"]), "unknown option `shiny`");
+    assert_eq!(
+        error(&["ss:70000"]),
+        "invalid supersampling `70000`: expected <n>[:<pattern>], where n is from 1 to 16, like 3 or 2:rotated"
+    );
+    assert_eq!(
+        error(&["aa:17:jittered"]),
+        "invalid adaptive anti-aliasing `17:jittered`: expected <n>[:<pattern>], where n is from 1 to 16, like 3 or 2:rotated"
+    );
+    assert_eq!(
+        error(&["julia:-0.8"]),
+        "invalid fractal `julia:-0.8`: expected `mandelbrot` or `julia:<re>,<im>`"
+    );
+    assert_eq!(
+        error(&["strategy:tiles:0x8"]),
+        "invalid strategy `tiles:0x8`: expected `singlethreaded`, `segments`, `rows`, `pixels` or `tiles`"
+    );
+    assert_eq!(
+        error(&["interpolation:oklabb"]),
+        "invalid interpolation `oklabb`: expected `srgb`, `linear`, `hsv`, `hsl` or `oklab`"
+    );
+    for cycle in &["abc", "0", "log:x", "32:1"] {
+        assert_eq!(
+            error(&[&format!("cycle:{}", cycle)]),
+            format!("invalid cycle `{}`: expected {}", cycle, CYCLE_FORMAT)
+        );
+    }
+    assert_eq!(
+        error(&["inside:#zzz"]),
+        "invalid inside color `#zzz`: expected a hex color, like #000000"
+    );
+    assert_eq!(
+        error(&["interior:foo"]),
+        "invalid interior `foo`: expected `flat`, `magnitude`, `period` or `angle`"
+    );
+    assert_eq!(
+        error(&["--rotation", "left"]),
+        "invalid rotation `left`: expected a number of degrees, like 45 or -12.5"
+    );
+}
+
+#[test]
+fn test_parse_scene_flag() {
+    let path = std::env::temp_dir().join("threadpool_fractal_test_scene.toml");
+    let path = path.to_str().unwrap();
+    let saved = parse_flag_args(&to_args(&[
+        "--output", "saved.png",
+        "--size", "300x200",
+        "--center", "0.25,0.5",
+        "--rotation", "90",
+        "--palette", "usa",
+        "julia:-0.8,0.156",
+        "strategy:tiles:hilbert",
+        "--save-scene", path,
+    ])).unwrap();
+
+    assert_eq!(saved.save_scene.as_deref(), Some(path));
+
+    SceneFile::from_args(&saved).save(path).unwrap();
+
+    // A scene file sets everything it describes, and flags after it change it
+    let args = parse_flag_args(&to_args(&["--scene", path, "--iterations", "50"])).unwrap();
+
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(args.output_filename, "saved.png");
+    assert_eq!((args.image_width, args.image_height), (300, 200));
+    assert_eq!(args.viewport, saved.viewport);
+    assert_eq!(args.coloring.palette, crate::colors::usa());
+    assert_eq!(args.fractal, saved.fractal);
+    assert_eq!(args.strategy, saved.strategy);
+    assert_eq!(args.limit, 50);
+    assert_eq!(args.save_scene, None);
+
+    assert!(
+        parse_flag_args(&to_args(&["--scene", "no_such_scene.toml"])).err().unwrap()
+            .starts_with("couldn't read scene file `no_such_scene.toml`: ")
+    );
+}
+
+#[test]
+fn test_parse_from_image_flag() {
+    let path = std::env::temp_dir().join("threadpool_fractal_test_rerender.png");
+    let path = path.to_str().unwrap();
+    let rendered = parse_flag_args(&to_args(&[
+        "--output", path, "--size", "8x4", "--zoom", "2", "--iterations", "20", "julia:-0.8,0.156",
+    ])).unwrap();
+
+    crate::metadata::save_png(&RgbImage::new(8, 4), path, &SceneFile::from_args(&rendered)).unwrap();
+
+    // Images are re-rendered from the scene embedded in them, at any size...
+    let args = parse_flag_args(&to_args(&[
+        "--from-image", path, "--size", "16x8", "--output", "bigger.png",
+    ])).unwrap();
+
+    assert_eq!((args.image_width, args.image_height), (16, 8));
+    assert_eq!(args.viewport, rendered.viewport);
+    assert_eq!(args.limit, 20);
+    assert_eq!(args.fractal, rendered.fractal);
+
+    // ...but not over themselves, even when named another way
+    let error = parse_flag_args(&to_args(&["--from-image", path])).err().unwrap();
+    let dot_path = std::env::temp_dir().join(".").join("threadpool_fractal_test_rerender.png");
+    let dot_error = parse_flag_args(&to_args(&[
+        "--from-image", dot_path.to_str().unwrap(), "--output", path,
+    ])).err();
+
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(
+        error,
+        format!("re-rendering `{}` would overwrite it: pick another `--output`", path)
+    );
+    assert_eq!(
+        dot_error,
+        Some(format!("re-rendering `{}` would overwrite it: pick another `--output`", path))
+    );
+}
+
+#[test]
+fn test_parse_positional_args() {
+    let args = parse_positional_args(&to_args(&[
+        "frac.png", "2000x1000", "-2.0,1.0", "2.0,-1.0", "350", "6", "usa", "ss:2",
+    ])).unwrap();
+
+    assert_eq!(args.output_filename, "frac.png");
+    assert_eq!((args.image_width, args.image_height), (2000, 1000));
+    assert_eq!(
+        args.viewport.corners(),
+        (Complex { re: -2.0, im: 1.0 }, Complex { re: 2.0, im: -1.0 })
+    );
+    assert_eq!(args.limit, 350);
+    assert_eq!(args.threads, 6);
+    assert_eq!(args.coloring.palette, crate::colors::usa());
+    assert_eq!(args.supersampling, Supersampling::Grid(2));
+
+    // Corners that don't match the resolution's aspect ratio are spread apart
+    // until they do, instead of stretching the image
+    let args = parse_positional_args(&to_args(&[
+        "frac.png", "400x200", "-1.0,1.0", "1.0,-1.0", "350", "6", "usa",
+    ])).unwrap();
+
+    assert_eq!(
+        args.viewport.corners(),
+        (Complex { re: -2.0, im: 1.0 }, Complex { re: 2.0, im: -1.0 })
+    );
+
+    // Mistakes get messages instead of panics
+    let error = |args: &[&str]| parse_positional_args(&to_args(args)).err().unwrap();
+
+    assert_eq!(
+        error(&["frac.png", "2000x1000", "-2.0,1.0", "2.0,-1.0", "many", "6", "usa"]),
+        "invalid iteration limit `many`: expected a positive whole number, like 250"
+    );
+    assert!(
+        error(&["frac.png", "2000x1000", "-2.0,1.0", "2.0,-1.0", "350", "6", "plaid"])
+            .starts_with("unknown color theme `plaid`")
+    );
+    assert_eq!(
+        error(&["frac.png", "2000x1000"]),
+        "expected 7 arguments (or flags, like `--size`), but got 2"
+    );
+    assert_eq!(
+        error(&["frac.png", "0x600", "-2.0,1.0", "2.0,-1.0", "350", "6", "usa"]),
+        "invalid image resolution `0x600`: expected <width>x<height>, like 800x600"
+    );
+    assert_eq!(
+        error(&["frac.png", "800x0", "-2.0,1.0", "2.0,-1.0", "350", "6", "usa"]),
+        "invalid image resolution `800x0`: expected <width>x<height>, like 800x600"
+    );
+
+    // Swapped corners would render the view mirrored
+    assert_eq!(
+        error(&["frac.png", "2000x1000", "2.0,-1.0", "-2.0,1.0", "350", "6", "usa"]),
+        "a viewport's upper left corner must be above and left of its lower right corner"
+    );
+}
+
+/// Validates the arguments after `bench` and returns them as a
+/// `Command::Bench`
+fn parse_bench_args(args: &[String]) -> Result<Command, String> {
+    if args.len() < 4 {
+        return Err(format!("expected 4 arguments after `bench`, but got {}", args.len()));
+    }
+
+    let resolution = parse_resolution(&args[0], "image resolution")?;
+    let complex_upper_left_corner = require(
+        parse_complex(&args[1]), "upper left complex bound", &args[1], "<re>,<im>, like -2.0,1.0"
+    )?;
+    let complex_lower_right_corner = require(
+        parse_complex(&args[2]), "lower right complex bound", &args[2], "<re>,<im>, like 1.0,-1.0"
+    )?;
+    let mut scene = Scene {
+        fractal: FractalKind::Mandelbrot,
+        limit: parse_limit(&args[3])?,
+        viewport: Viewport::try_from_corners(complex_upper_left_corner, complex_lower_right_corner)?
+            .fit(resolution.0, resolution.1),
+        coloring: Coloring::new(crate::colors::fire()),
+        supersampling: Supersampling::None,
+    };
+    let mut thread_counts = crate::bench::default_thread_counts();
+    let mut runs = 3;
+    let mut format = ReportFormat::Table;
+
+    for option in &args[4..] {
+        if option == "smooth" {
+            scene.coloring.smooth = true;
+        }
+        else if let Some(pattern) = option.strip_prefix("ss:") {
+            scene.supersampling = require(
+                parse_sampling_pattern(pattern), "supersampling", pattern, SAMPLING_FORMAT
+            )?;
+        }
+        else if let Some(parsed_fractal) = parse_fractal(option) {
+            scene.fractal = parsed_fractal;
+        }
+        else if let Some(parsed_thread_counts) = parse_thread_counts(option) {
+            thread_counts = parsed_thread_counts;
+        }
+        else if let Some(parsed_runs) = option.strip_prefix("runs:") {
+            runs = require(
+                parsed_runs.parse().ok().filter(|&runs| runs > 0),
+                "run count", parsed_runs, "a positive whole number"
+            )?;
+        }
+        else if let Some(parsed_format) = parse_report_format(option) {
+            format = parsed_format;
+        }
+        else {
+            return Err(format!("unknown option `{}`", option));
+        }
+    }
+
+    let bench = Bench {
+        scene,
+        image_width: resolution.0,
+        image_height: resolution.1,
+        strategies: vec![
+            RenderStrategy::Singlethreaded,
+            RenderStrategy::PooledPixels,
+            RenderStrategy::PreallocatedSegments,
+            RenderStrategy::PooledRows,
+            RenderStrategy::PooledTiles {
+                tile_size: crate::tiles::DEFAULT_TILE_SIZE,
+                order: TileOrder::Spiral
+            },
+        ],
+        thread_counts,
+        runs,
+    };
+
+    return Ok(Command::Bench { bench, format });
+}
+
+/// Parses a list of thread counts to benchmark with, like `"threads:1,2,8"`
+fn parse_thread_counts(s: &str) -> Option<Vec<usize>> {
+    return s.strip_prefix("threads:")?
+        .split(',')
+        .map(|threads| parse_threads(threads).map(|threads| threads as usize))
+        .collect();
+}
+
+/// Parses a benchmark report format, like `"format:csv"`
+fn parse_report_format(s: &str) -> Option<ReportFormat> {
+    match s.strip_prefix("format:")? {
+        "table" => Some(ReportFormat::Table),
+        "csv" => Some(ReportFormat::Csv),
+        "json" => Some(ReportFormat::Json),
+        _ => None
+    }
+}
+
+#[test]
+fn test_parse_bench_options() {
+    assert_eq!(parse_thread_counts("threads:1,2,8"), Some(vec![1, 2, 8]));
+    assert_eq!(parse_thread_counts("threads:4"), Some(vec![4]));
+    assert_eq!(parse_thread_counts("threads:1,0"), None);
+    assert_eq!(parse_thread_counts("threads:"), None);
+    assert_eq!(parse_report_format("format:json"), Some(ReportFormat::Json));
+    assert_eq!(parse_report_format("format:xml"), None);
+
+    // Empty images have nothing to time
+    let error = |args: &[&str]| parse_bench_args(&to_args(args)).err().unwrap();
+
+    assert_eq!(
+        error(&["0x600", "-2.0,1.0", "2.0,-1.0", "350"]),
+        "invalid image resolution `0x600`: expected <width>x<height>, like 800x600"
+    );
+    assert_eq!(
+        error(&["800x0", "-2.0,1.0", "2.0,-1.0", "350"]),
+        "invalid image resolution `800x0`: expected <width>x<height>, like 800x600"
+    );
+
+    // ...and no runs leave no times to take the median of
+    assert_eq!(
+        error(&["800x600", "-2.0,1.0", "2.0,-1.0", "350", "runs:0"]),
+        "invalid run count `0`: expected a positive whole number"
+    );
 }
 
 /// Parses the string `s` to read a coordinate pair, like `"400x600"` or `"1.0,0.5"`,
@@ -131,10 +1006,7 @@ fn test_parse_pair() {
 
 /// Parses a pair of floats separated by a comma as a complex number
 pub fn parse_complex(s: &str) -> Option<Complex<f64>> {
-    match parse_pair(s, ',') {
-        Some((re, im)) => Some(Complex { re, im }),
-        None => None
-    }
+    parse_pair(s, ',').map(|(re, im)| Complex { re, im })
 }
 
 #[test]
@@ -143,88 +1015,658 @@ fn test_parse_complex() {
     assert_eq!(parse_complex(",-0.0625"), None);
 }
 
-/// Renders a rectangle of the Mandelbrot set with a single thread
-///
-/// Every pixel in `pixels` is treated as a point on the complex plane, is
-/// tested for membership in the set, and colored accordingly per the given
-/// `color_theme`.
+/// Parses a thread count, which is either a positive number or `"auto"` for
+/// as many threads as this process can run in parallel
+pub fn parse_threads(s: &str) -> Option<u32> {
+    if s == "auto" {
+        return Some(crate::threadpool::available_threads() as u32);
+    }
+
+    match s.parse() {
+        Ok(threads) if threads > 0 => Some(threads),
+        _ => None
+    }
+}
+
+#[test]
+fn test_parse_threads() {
+    assert_eq!(parse_threads("6"), Some(6));
+    assert_eq!(parse_threads("auto"), Some(crate::threadpool::available_threads() as u32));
+    assert_eq!(parse_threads("0"), None);
+    assert_eq!(parse_threads("-2"), None);
+    assert_eq!(parse_threads("many"), None);
+}
+
+/// The fractals `parse_fractal` knows, for error messages
+const FRACTAL_FORMAT: &str = "`mandelbrot` or `julia:<re>,<im>`";
+
+/// Parses a fractal name, like `"mandelbrot"` or `"julia:-0.8,0.156"`, where
+/// the Julia set's constant `c` follows the colon
+pub fn parse_fractal(s: &str) -> Option<FractalKind> {
+    if s == "mandelbrot" {
+        return Some(FractalKind::Mandelbrot);
+    }
+
+    match s.strip_prefix("julia:") {
+        Some(c) => parse_complex(c).map(FractalKind::Julia),
+        None => None
+    }
+}
+
+/// Parses a supersampling option, like `"ss:3"` or `"ss:2:rotated"`, where
+/// the number of samples per side of a pixel follows the first colon and the
+/// optional sampling pattern follows the second
+pub fn parse_supersampling(s: &str) -> Option<Supersampling> {
+    return parse_sampling_pattern(s.strip_prefix("ss:")?);
+}
+
+/// Parses an adaptive anti-aliasing option, like `"aa:3"` or
+/// `"aa:2:rotated"`, which is formatted like a supersampling option
+pub fn parse_adaptive_antialiasing(s: &str) -> Option<Supersampling> {
+    return parse_sampling_pattern(s.strip_prefix("aa:")?);
+}
+
+/// The most samples per side of a pixel a supersampling option can ask for,
+/// which is already 256 samples per pixel
+const MAX_SAMPLES_PER_SIDE: u32 = 16;
+
+/// How the `<n>[:<pattern>]` part of a supersampling option is written
+const SAMPLING_FORMAT: &str = "<n>[:<pattern>], where n is from 1 to 16, like 3 or 2:rotated";
+
+/// Parses the `<n>[:<pattern>]` part of a supersampling option, where `n` is
+/// from 1 to `MAX_SAMPLES_PER_SIDE`
+fn parse_sampling_pattern(option: &str) -> Option<Supersampling> {
+    let (n, pattern) = match option.find(':') {
+        Some(index) => (&option[..index], &option[index + 1..]),
+        None => (option, "grid")
+    };
+    let n: u32 = match n.parse() {
+        Ok(n) if n > 0 && n <= MAX_SAMPLES_PER_SIDE => n,
+        _ => return None
+    };
+
+    match pattern {
+        "grid" => Some(Supersampling::Grid(n)),
+        "jittered" => Some(Supersampling::Jittered(n)),
+        "rotated" => Some(Supersampling::RotatedGrid(n)),
+        _ => None
+    }
+}
+
+#[test]
+fn test_parse_supersampling() {
+    assert_eq!(parse_supersampling("ss:3"), Some(Supersampling::Grid(3)));
+    assert_eq!(parse_supersampling("ss:3:grid"), Some(Supersampling::Grid(3)));
+    assert_eq!(parse_supersampling("ss:2:jittered"), Some(Supersampling::Jittered(2)));
+    assert_eq!(parse_supersampling("ss:2:rotated"), Some(Supersampling::RotatedGrid(2)));
+    assert_eq!(parse_supersampling("ss:16:rotated"), Some(Supersampling::RotatedGrid(16)));
+    assert_eq!(parse_supersampling("ss:0"), None);
+    assert_eq!(parse_supersampling("ss:17"), None);
+    assert_eq!(parse_supersampling("ss:70000"), None);
+    assert_eq!(parse_supersampling("ss:"), None);
+    assert_eq!(parse_supersampling("ss:2:hexagonal"), None);
+    assert_eq!(parse_supersampling("smooth"), None);
+    assert_eq!(parse_supersampling("aa:3"), None);
+}
+
+#[test]
+fn test_parse_adaptive_antialiasing() {
+    assert_eq!(parse_adaptive_antialiasing("aa:3"), Some(Supersampling::Grid(3)));
+    assert_eq!(parse_adaptive_antialiasing("aa:4:rotated"), Some(Supersampling::RotatedGrid(4)));
+    assert_eq!(parse_adaptive_antialiasing("aa:0"), None);
+    assert_eq!(parse_adaptive_antialiasing("aa:70000"), None);
+    assert_eq!(parse_adaptive_antialiasing("ss:3"), None);
+}
+
+#[test]
+fn test_parse_fractal() {
+    assert_eq!(parse_fractal("mandelbrot"), Some(FractalKind::Mandelbrot));
+    assert_eq!(
+        parse_fractal("julia:-0.8,0.156"),
+        Some(FractalKind::Julia(Complex { re: -0.8, im: 0.156 }))
+    );
+    assert_eq!(parse_fractal("julia:"), None);
+    assert_eq!(parse_fractal("julia"), None);
+    assert_eq!(parse_fractal("burning_ship"), None);
+}
+
+/// The color spaces `parse_interpolation` knows, for error messages
+const INTERPOLATION_NAMES: &str = "`srgb`, `linear`, `hsv`, `hsl` or `oklab`";
+
+/// Parses an interpolation option, like `"interpolation:oklab"`, where the
+/// color space to blend a palette's colors in follows the colon
+pub fn parse_interpolation(s: &str) -> Option<Interpolation> {
+    match s.strip_prefix("interpolation:")? {
+        "srgb" => Some(Interpolation::Srgb),
+        "linear" => Some(Interpolation::LinearRgb),
+        "hsv" => Some(Interpolation::Hsv),
+        "hsl" => Some(Interpolation::Hsl),
+        "oklab" => Some(Interpolation::Oklab),
+        _ => None
+    }
+}
+
+#[test]
+fn test_parse_interpolation() {
+    assert_eq!(parse_interpolation("interpolation:srgb"), Some(Interpolation::Srgb));
+    assert_eq!(parse_interpolation("interpolation:oklab"), Some(Interpolation::Oklab));
+    assert_eq!(parse_interpolation("interpolation:lab"), None);
+    assert_eq!(parse_interpolation("oklab"), None);
+
+    // Color spaces print the way they're parsed
+    for &interpolation in &[Interpolation::LinearRgb, Interpolation::Hsv, Interpolation::Hsl] {
+        assert_eq!(
+            parse_interpolation(&format!("interpolation:{}", interpolation)),
+            Some(interpolation)
+        );
+    }
+}
+
+/// What follows `inside:` in an inside color option, for error messages
+const INSIDE_FORMAT: &str = "a hex color, like #000000";
+
+/// Parses an inside color option, like `"inside:#102030"`, where the hex
+/// color follows the colon
+pub fn parse_inside(s: &str) -> Option<Rgb<u8>> {
+    return crate::palette_file::parse_hex_color(s.strip_prefix("inside:")?);
+}
+
+#[test]
+fn test_parse_inside() {
+    assert_eq!(parse_inside("inside:#102030"), Some(Rgb([16, 32, 48])));
+    assert_eq!(parse_inside("inside:ffffff"), Some(Rgb([255, 255, 255])));
+    assert_eq!(parse_inside("inside:white"), None);
+    assert_eq!(parse_inside("#102030"), None);
+}
+
+/// The interiors `parse_interior` knows, for error messages
+const INTERIOR_NAMES: &str = "`flat`, `magnitude`, `period` or `angle`";
+
+/// Parses an interior option, like `"interior:period"`, where how to color the
+/// inside of the set follows the colon
+pub fn parse_interior(s: &str) -> Option<Interior> {
+    match s.strip_prefix("interior:")? {
+        "flat" => Some(Interior::Flat),
+        "magnitude" => Some(Interior::Magnitude),
+        "period" => Some(Interior::Period),
+        "angle" => Some(Interior::Angle),
+        _ => None
+    }
+}
+
+#[test]
+fn test_parse_interior() {
+    assert_eq!(parse_interior("interior:flat"), Some(Interior::Flat));
+    assert_eq!(parse_interior("interior:period"), Some(Interior::Period));
+    assert_eq!(parse_interior("interior:bulbs"), None);
+    assert_eq!(parse_interior("period"), None);
+
+    // Interiors print the way they're parsed
+    for &interior in &[Interior::Magnitude, Interior::Angle] {
+        assert_eq!(parse_interior(&format!("interior:{}", interior)), Some(interior));
+    }
+}
+
+/// What follows `cycle:` in a cycle option, for error messages
+const CYCLE_FORMAT: &str = "<k>[:<offset>] or log:<k>[:<offset>], like 32 or log:0.5:0.25";
+
+/// Parses a cycle option, like `"cycle:32"` or `"cycle:log:0.5:0.25"`, where
+/// the period follows the colon (after `log:`, for a log scale), and then
+/// optionally the offset, from 0 to 1
+pub fn parse_cycle(s: &str) -> Option<Cycle> {
+    let mut parts = s.strip_prefix("cycle:")?.split(':');
+    let mut period = parts.next()?;
+    let log = period == "log";
+
+    if log {
+        period = parts.next()?;
+    }
+
+    let period: f64 = period.parse().ok().filter(|&period: &f64| period > 0.0 && period.is_finite())?;
+    let offset: f64 = match parts.next() {
+        Some(offset) => offset.parse().ok().filter(|offset| (0.0..1.0).contains(offset))?,
+        None => 0.0
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    return Some(Cycle { period, log, offset });
+}
+
+#[test]
+fn test_parse_cycle() {
+    assert_eq!(parse_cycle("cycle:32"), Some(Cycle { period: 32.0, log: false, offset: 0.0 }));
+    assert_eq!(parse_cycle("cycle:32:0.25"), Some(Cycle { period: 32.0, log: false, offset: 0.25 }));
+    assert_eq!(parse_cycle("cycle:log:0.5"), Some(Cycle { period: 0.5, log: true, offset: 0.0 }));
+    assert_eq!(parse_cycle("cycle:log:0.5:0.5"), Some(Cycle { period: 0.5, log: true, offset: 0.5 }));
+    assert_eq!(parse_cycle("cycle:0"), None);
+    assert_eq!(parse_cycle("cycle:32:1.5"), None);
+    assert_eq!(parse_cycle("cycle:log"), None);
+    assert_eq!(parse_cycle("cycle:32:0.25:1"), None);
+    assert_eq!(parse_cycle("32"), None);
+
+    // Cycles print the way they're parsed
+    for cycle in &["32", "32:0.25", "log:0.5", "log:1.5:0.75"] {
+        assert_eq!(parse_cycle(&format!("cycle:{}", cycle)).unwrap().to_string(), *cycle);
+    }
+}
+
+/// The strategies `parse_strategy` knows, for error messages
+const STRATEGY_NAMES: &str = "`singlethreaded`, `segments`, `rows`, `pixels` or `tiles`";
+
+/// Parses a render strategy option, like `"strategy:rows"` or
+/// `"strategy:tiles:64x32:hilbert"`, where the strategy's name follows the
+/// first colon and, for tiles, the optional tile size and order follow that
+pub fn parse_strategy(s: &str) -> Option<RenderStrategy> {
+    let strategy = s.strip_prefix("strategy:")?;
+
+    match strategy {
+        "singlethreaded" => return Some(RenderStrategy::Singlethreaded),
+        "segments" => return Some(RenderStrategy::PreallocatedSegments),
+        "rows" => return Some(RenderStrategy::PooledRows),
+        "pixels" => return Some(RenderStrategy::PooledPixels),
+        _ => {}
+    }
+
+    let mut tile_options = strategy.strip_prefix("tiles")?.split(':');
+    let mut tile_size = crate::tiles::DEFAULT_TILE_SIZE;
+    let mut order = TileOrder::Spiral;
+
+    // Anything after "tiles" needs a colon first
+    if tile_options.next() != Some("") {
+        return None;
+    }
+
+    for tile_option in tile_options {
+        match tile_option {
+            "row-major" => order = TileOrder::RowMajor,
+            "spiral" => order = TileOrder::Spiral,
+            "hilbert" => order = TileOrder::Hilbert,
+            _ => match parse_pair(tile_option, 'x') {
+                Some((width, height)) if width > 0 && height > 0 => tile_size = (width, height),
+                _ => return None
+            }
+        }
+    }
+
+    return Some(RenderStrategy::PooledTiles { tile_size, order });
+}
+
+#[test]
+fn test_parse_strategy() {
+    assert_eq!(parse_strategy("strategy:singlethreaded"), Some(RenderStrategy::Singlethreaded));
+    assert_eq!(parse_strategy("strategy:segments"), Some(RenderStrategy::PreallocatedSegments));
+    assert_eq!(parse_strategy("strategy:rows"), Some(RenderStrategy::PooledRows));
+    assert_eq!(parse_strategy("strategy:pixels"), Some(RenderStrategy::PooledPixels));
+    assert_eq!(
+        parse_strategy("strategy:tiles"),
+        Some(RenderStrategy::PooledTiles {
+            tile_size: crate::tiles::DEFAULT_TILE_SIZE,
+            order: TileOrder::Spiral
+        })
+    );
+    assert_eq!(
+        parse_strategy("strategy:tiles:64x16:hilbert"),
+        Some(RenderStrategy::PooledTiles { tile_size: (64, 16), order: TileOrder::Hilbert })
+    );
+    assert_eq!(
+        parse_strategy("strategy:tiles:row-major"),
+        Some(RenderStrategy::PooledTiles {
+            tile_size: crate::tiles::DEFAULT_TILE_SIZE,
+            order: TileOrder::RowMajor
+        })
+    );
+    assert_eq!(parse_strategy("strategy:tiles:0x16"), None);
+    assert_eq!(parse_strategy("strategy:tiles:zigzag"), None);
+    assert_eq!(parse_strategy("strategy:tilesets"), None);
+    assert_eq!(parse_strategy("strategy:columns"), None);
+    assert_eq!(parse_strategy("rows"), None);
+
+    // Strategies print the way they're parsed
+    for strategy in &[
+        RenderStrategy::Singlethreaded,
+        RenderStrategy::PooledPixels,
+        RenderStrategy::PooledTiles { tile_size: (8, 4), order: TileOrder::RowMajor },
+    ] {
+        assert_eq!(parse_strategy(&format!("strategy:{}", strategy)), Some(*strategy));
+    }
+}
+
+/// What to render: everything needed to work out the color of any pixel of
+/// an image of a fractal
 ///
+/// `fractal` is the formula each pixel is iterated with, like `Mandelbrot` or
+/// `Julia`
 /// `limit` is the maximum number of iterations used to test each pixel
 /// (the higher it is, the more accurate the test)
-/// `complex_upper_left_bound` and `complex_lower_right_bound` designate the
-/// area on the complex plane covered by the rectangle
+/// `viewport` is the area on the complex plane covered by the image
+/// `coloring` is the palette and method we'll use to color pixels
+/// `supersampling` is how many points of the complex plane are sampled per
+/// pixel, and where
+#[derive(Clone, Debug)]
+pub struct Scene<F> {
+    pub fractal: F,
+    pub limit: u32,
+    pub viewport: Viewport,
+    pub coloring: Coloring,
+    pub supersampling: Supersampling,
+}
+
+impl<F: Fractal> Scene<F> {
+    /// Returns the color of the pixel at `pixel_coords` in an image with the
+    /// given `width` and `height`
+    ///
+    /// When supersampling, this is the average color of all the samples.
+    /// Otherwise, it's the color of the point `single_sample_point` gives.
+    pub fn pixel_color(&self, pixel_coords: (u32, u32), width: u32, height: u32) -> Rgb<u8> {
+        // One sample needs no averaging, so skip gathering it up with the rest
+        if self.supersampling == Supersampling::None {
+            let sample = crate::antialiasing::single_sample_point(pixel_coords);
+
+            return self.sample_color(sample, width, height);
+        }
+
+        let colors: Vec<Rgb<u8>> = self.supersampling.sample_points(pixel_coords)
+            .into_iter()
+            .map(|sample| self.sample_color(sample, width, height))
+            .collect();
+
+        return crate::antialiasing::average_colors(&colors);
+    }
+
+    /// Returns the color of the point at the fractional pixel coordinates
+    /// `sample`
+    fn sample_color(&self, sample: (f64, f64), width: u32, height: u32) -> Rgb<u8> {
+        let complex_point = self.viewport.subpixel_to_complex_point(sample, width, height);
+
+        // Only some ways of coloring the inside of the set need the orbit, which
+        // is gathered while iterating, so the inside isn't iterated twice
+        if self.coloring.interior.uses_orbit() {
+            let (escape, orbit) = self.fractal.orbit(complex_point, self.limit);
+
+            if !escape.escaped(self.limit) {
+                return self.coloring.interior_color(&escape, &orbit);
+            }
+
+            return self.coloring.color(&escape, self.limit);
+        }
+
+        let escape = self.fractal.escape(complex_point, self.limit);
+
+        return self.coloring.color(&escape, self.limit);
+    }
+}
+
+/// A way of splitting up the work of rendering an image between threads
+///
+/// From slowest to fastest, roughly, since how much faster each one is depends
+/// on the view and the machine.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderStrategy {
+    /// 🐢 Just one thread, doing all the work, one pixel at a time
+    ///
+    /// See `render_singlethreaded`.
+    Singlethreaded,
+    /// 🐇-- All the individual pixels, tossed into the thread pool
+    ///
+    /// Since finer (row) granularity is faster than broader (large image
+    /// segment) granularity, you'd think that *even finer* (pixel) granularity
+    /// would be faster still! But, actually, this is slower than row-based and
+    /// segment-based multithreading, since a pixel takes so little time to
+    /// render that boxing, queueing, and handing out one job per pixel costs
+    /// about as much as the rendering itself.
+    ///
+    /// See `render_multithreaded_pooled_pixels`.
+    PooledPixels,
+    /// 🐇 The image, partitioned into one segment of pixels per thread,
+    /// preallocating the workload in a broad way
+    ///
+    /// This is faster since threads (on a multi-core CPU) are doing the work in
+    /// parallel. The pool isn't used, except to decide how many threads to
+    /// spawn.
+    ///
+    /// See `render_multithreaded_preallocated_segments`.
+    PreallocatedSegments,
+    /// 🐇++ The image, partitioned into rows of pixels, all tossed into the
+    /// thread pool for threads to snatch up, process, and snatch up more when
+    /// they finish, until no more rows remain in the pool
+    ///
+    /// This is even faster (slightly) since:
+    ///
+    /// 1. Some pixels get rendered faster than others
+    ///
+    /// 2. With row-by-row granularity, the workload is more evenly
+    ///    distributed among the threads
+    ///
+    /// The reason for 1 is that pixels that correspond to complex points which
+    /// escape the set right away are rendered very quickly, since they're done
+    /// once they escape. Call these "fast pixels".
+    ///
+    /// The reason for 2 is that rows replete with fast pixels are rendered very
+    /// quickly. When threads are assigned large segments of pixels, a thread
+    /// that got a "fast" segment made up of fast rows finishes and then idles
+    /// while threads that got "slow" segments keep crunching. With row-by-row
+    /// granularity, such idle threads instead pick up another row to process.
+    ///
+    /// See `render_multithreaded_pooled_rows`.
+    PooledRows,
+    /// 🐇+ The image, partitioned into square-ish tiles of `tile_size` (width,
+    /// height) pixels, tossed into the thread pool in `order`, like rows
+    ///
+    /// Tiles keep each thread's pixels close together, which should make this
+    /// about as fast as rows, and the order makes it look nicer while it fills
+    /// in.
+    ///
+    /// See `render_multithreaded_pooled_tiles`.
+    PooledTiles { tile_size: (u32, u32), order: TileOrder },
+}
+
+impl RenderStrategy {
+    /// Renders `scene` into `pixels` this way, with the threads of `pool`
+    ///
+    /// `PreallocatedSegments` spawns threads of its own instead, one for each
+    /// of the pool's.
+    pub fn render<F: Fractal + Sync>(&self, scene: &Scene<F>, pixels: &mut RgbImage, pool: &ThreadPool) {
+        match *self {
+            RenderStrategy::Singlethreaded => render_singlethreaded(scene, pixels),
+            RenderStrategy::PooledPixels => render_multithreaded_pooled_pixels(scene, pixels, pool),
+            RenderStrategy::PreallocatedSegments => {
+                render_multithreaded_preallocated_segments(scene, pixels, pool.size() as u32)
+            }
+            RenderStrategy::PooledRows => render_multithreaded_pooled_rows(scene, pixels, pool),
+            RenderStrategy::PooledTiles { tile_size, order } => {
+                render_multithreaded_pooled_tiles(scene, pixels, pool, tile_size, order)
+            }
+        }
+    }
+}
+
+/// Writes the strategy the way `parse_strategy` reads it, minus the
+/// `strategy:` prefix
+impl fmt::Display for RenderStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderStrategy::Singlethreaded => write!(f, "singlethreaded"),
+            RenderStrategy::PooledPixels => write!(f, "pixels"),
+            RenderStrategy::PreallocatedSegments => write!(f, "segments"),
+            RenderStrategy::PooledRows => write!(f, "rows"),
+            RenderStrategy::PooledTiles { tile_size, order } => {
+                let order = match order {
+                    TileOrder::RowMajor => "row-major",
+                    TileOrder::Spiral => "spiral",
+                    TileOrder::Hilbert => "hilbert",
+                };
+
+                write!(f, "tiles:{}x{}:{}", tile_size.0, tile_size.1, order)
+            }
+        }
+    }
+}
+
+/// Renders `scene` with a single thread
+///
+/// Every pixel in `pixels` is treated as a point on the complex plane, is
+/// tested for membership in the set, and colored accordingly per the scene's
+/// `coloring`.
+///
+/// `scene` describes what to render
 /// `pixels` is the output buffer, containing a rectangle of pixels
-// `color_theme` is the palette we'll use to color pixels
-pub fn render_singlethreaded(
-    limit: u32,
-    complex_upper_left_corner: Complex<f64>,
-    complex_lower_right_corner: Complex<f64>,
-    pixels: Arc<Mutex<RgbImage>>,
-    color_theme: Vec<Rgb<u8>>
-) {
-    let width = pixels.lock().unwrap().width();
-    let height = pixels.lock().unwrap().height();
-
-    for (x, y, pixel) in pixels.lock().unwrap().enumerate_pixels_mut() {
-        let complex_point = crate::mandelbrot::pixel_to_complex_point(
-            (x, y),
-            width, height,
-            complex_upper_left_corner,
-            complex_lower_right_corner
-        );
-        let iterations = crate::mandelbrot::escape_time(complex_point, limit);
+pub fn render_singlethreaded<F: Fractal>(scene: &Scene<F>, pixels: &mut RgbImage) {
+    let width = pixels.width();
+    let height = pixels.height();
 
-        *pixel = crate::colors::iterations_to_color(
-            iterations,
-            limit,
-            &color_theme
-        );
+    for (x, y, pixel) in pixels.enumerate_pixels_mut() {
+        *pixel = scene.pixel_color((x, y), width, height);
     }
 }
 
-/// Container for a pixel and its coordinates in the output image
-struct PixelData {
-    pub pixel: Rgb<u8>,
-    pub x: u32,
-    pub y: u32,
+/// Test fractal that escapes right away left of the imaginary axis and never
+/// escapes on or right of it
+#[cfg(test)]
+#[derive(Clone)]
+struct HalfPlane;
+
+#[cfg(test)]
+impl Fractal for HalfPlane {
+    fn escape(&self, point: Complex<f64>, limit: u32) -> Escape {
+        if point.re < 0.0 {
+            return Escape { iterations: 0, norm_sqr: 16.0 };
+        }
+
+        return Escape { iterations: limit, norm_sqr: 0.0 };
+    }
 }
 
-/// Splits `pixels` into segments such that there's one segment per thread
-fn divide_image_into_segments(
-    pixels: &mut RgbImage,
+#[test]
+fn test_render_custom_fractal() {
+    let mut pixels = RgbImage::new(4, 1);
+    let scene = Scene {
+        fractal: HalfPlane,
+        limit: 10,
+        viewport: Viewport::from_corners(
+            Complex { re: -1.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        coloring: Coloring::new(crate::colors::grayscale()),
+        supersampling: Supersampling::None,
+    };
+
+    render_singlethreaded(&scene, &mut pixels);
+
+    assert_eq!(*pixels.get_pixel(0, 0), Rgb([255, 255, 255]));
+    assert_eq!(*pixels.get_pixel(1, 0), Rgb([255, 255, 255]));
+    assert_eq!(*pixels.get_pixel(2, 0), Rgb([0, 0, 0]));
+    assert_eq!(*pixels.get_pixel(3, 0), Rgb([0, 0, 0]));
+
+    // Without orbits to go by, the inside is the plain inside color
+    let scene = Scene {
+        coloring: Coloring {
+            inside: Rgb([16, 32, 48]),
+            interior: Interior::Period,
+            ..Coloring::new(crate::colors::grayscale())
+        },
+        ..scene
+    };
+
+    render_singlethreaded(&scene, &mut pixels);
+
+    assert_eq!(*pixels.get_pixel(3, 0), Rgb([16, 32, 48]));
+}
+
+#[test]
+fn test_render_supersampled() {
+    // The imaginary axis runs through the middle of the middle pixel
+    let mut pixels = RgbImage::new(3, 1);
+    let scene = Scene {
+        fractal: HalfPlane,
+        limit: 10,
+        viewport: Viewport::from_corners(
+            Complex { re: -1.5, im: 0.5 },
+            Complex { re: 1.5, im: -0.5 }
+        ),
+        coloring: Coloring::new(crate::colors::grayscale()),
+        supersampling: Supersampling::Grid(4),
+    };
+
+    render_singlethreaded(&scene, &mut pixels);
+
+    // Half of the middle pixel's samples are white, and half are black
+    assert_eq!(*pixels.get_pixel(0, 0), Rgb([255, 255, 255]));
+    assert_eq!(*pixels.get_pixel(1, 0), Rgb([128, 128, 128]));
+    assert_eq!(*pixels.get_pixel(2, 0), Rgb([0, 0, 0]));
+
+    // All strategies agree
+    let mut pooled_pixels = RgbImage::new(3, 1);
+
+    render_multithreaded_pooled_pixels(&scene, &mut pooled_pixels, &ThreadPool::new(2));
+
+    assert_eq!(pooled_pixels, pixels);
+}
+
+/// A run of consecutive pixels of the output image, in row-major order,
+/// borrowed so that a thread can render them without locking the whole image
+struct Segment<'a> {
+    /// Index of the segment's first pixel in the image
+    start: usize,
+    /// Width of the whole image, to tell the segment's pixels' coordinates
     width: u32,
-    height: u32,
-    threads: u32
-) -> Vec<Vec<PixelData>> {
-    let mut segments: Vec<Vec<PixelData>> = Vec::with_capacity(threads as usize);
-    let total_pixels = width * height;
-    let pixels_per_segment = (total_pixels / threads + 1) as usize;
+    /// The segment's pixels' channels, three (RGB) per pixel
+    pixels: &'a mut [u8],
+}
 
-    for _ in 0..threads {
-        segments.push(Vec::with_capacity(pixels_per_segment as usize));
+impl<'a> Segment<'a> {
+    /// Returns the number of pixels in the segment
+    fn len(&self) -> usize {
+        return self.pixels.len() / 3;
     }
 
-    // Assign each segment its pixels
-    let mut pixels_assigned = 0;
-    let mut curr_segment = 0;
+    /// Returns the coordinates in the whole image of the segment's `i`th pixel
+    fn coords(&self, i: usize) -> (u32, u32) {
+        let index = self.start + i;
+        let width = self.width as usize;
 
-    for (x, y, pixel) in pixels.enumerate_pixels_mut() {
-        segments[curr_segment].push(PixelData {
-            pixel: *pixel,
-            x: x,
-            y: y
-        });
+        return ((index % width) as u32, (index / width) as u32);
+    }
 
-        pixels_assigned += 1;
+    /// Renders the segment's `i`th pixel of `scene`, whose image is `height`
+    /// pixels tall
+    fn render_pixel<F: Fractal>(&mut self, i: usize, scene: &Scene<F>, height: u32) {
+        let pixel = scene.pixel_color(self.coords(i), self.width, height);
+
+        self.pixels[i * 3..i * 3 + 3].copy_from_slice(&pixel.0);
+    }
 
-        if pixels_assigned == pixels_per_segment {
-            // Begin filling next segment
-            curr_segment += 1;
-            pixels_assigned = 0;
+    /// Renders all of the segment's pixels of `scene`, whose image is `height`
+    /// pixels tall
+    fn render<F: Fractal>(&mut self, scene: &Scene<F>, height: u32) {
+        for i in 0..self.len() {
+            self.render_pixel(i, scene, height);
         }
     }
+}
 
-    return segments;
+/// Splits `pixels` into segments such that there's (at most) one segment per
+/// thread
+fn divide_image_into_segments(
+    pixels: &mut RgbImage,
+    width: u32,
+    height: u32,
+    threads: u32
+) -> Vec<Segment<'_>> {
+    // As `usize`, since big images have more pixels than fit in a `u32`
+    let total_pixels = width as usize * height as usize;
+    let pixels_per_segment = total_pixels / threads as usize + 1;
+
+    return pixels.chunks_mut(pixels_per_segment * 3)
+        .enumerate()
+        .map(|(i, chunk)| Segment {
+            start: i * pixels_per_segment,
+            width,
+            pixels: chunk,
+        })
+        .collect();
 }
 
 #[test]
@@ -251,98 +1693,54 @@ fn test_divide_image_into_segments() {
     assert_eq!(segments[4].len(), 1);
 
     // Correct pixel coordinates in first segment
-    assert_eq!(segments[0][0].x, 0);
-    assert_eq!(segments[0][0].y, 0);
-
-    assert_eq!(segments[0][1].x, 1);
-    assert_eq!(segments[0][1].y, 0);
+    assert_eq!(segments[0].coords(0), (0, 0));
+    assert_eq!(segments[0].coords(1), (1, 0));
 
     // Correct pixel coordinates in second segment
-    assert_eq!(segments[1][0].x, 2);
-    assert_eq!(segments[1][0].y, 0);
-
-    assert_eq!(segments[1][1].x, 0);
-    assert_eq!(segments[1][1].y, 1);
+    assert_eq!(segments[1].coords(0), (2, 0));
+    assert_eq!(segments[1].coords(1), (0, 1));
 
     // Correct pixel coordinates in third segment
-    assert_eq!(segments[2][0].x, 1);
-    assert_eq!(segments[2][0].y, 1);
-
-    assert_eq!(segments[2][1].x, 2);
-    assert_eq!(segments[2][1].y, 1);
+    assert_eq!(segments[2].coords(0), (1, 1));
+    assert_eq!(segments[2].coords(1), (2, 1));
 
     // Correct pixel coordinates in fourth segment
-    assert_eq!(segments[3][0].x, 0);
-    assert_eq!(segments[3][0].y, 2);
-
-    assert_eq!(segments[3][1].x, 1);
-    assert_eq!(segments[3][1].y, 2);
+    assert_eq!(segments[3].coords(0), (0, 2));
+    assert_eq!(segments[3].coords(1), (1, 2));
 
     // Correct pixel coordinates in fifth segment
-    assert_eq!(segments[4][0].x, 2);
-    assert_eq!(segments[4][0].y, 2);
+    assert_eq!(segments[4].coords(0), (2, 2));
+
+    // Pixels of big images can be past where a `u32` index would overflow
+    let segment = Segment { start: 70000 * 70000 - 1, width: 70000, pixels: &mut [] };
+
+    assert_eq!(segment.coords(0), (69999, 69999));
 }
 
-/// Renders a rectangle of the Mandelbrot set with `threads` threads by
+/// Renders `scene` into `pixels` with `threads` threads by
 /// breaking up the pixels into `threads` segments so that each thread will
 /// have one segment to process
-pub fn render_multithreaded_preallocated_segments(
-    limit: u32,
-    complex_upper_left_corner: Complex<f64>,
-    complex_lower_right_corner: Complex<f64>,
-    pixels: Arc<Mutex<RgbImage>>,
-    threads: u32,
-    color_theme: Vec<Rgb<u8>>
-) {
-    let width = pixels.lock().unwrap().width();
-    let height = pixels.lock().unwrap().height();
+pub fn render_multithreaded_preallocated_segments<F>(
+    scene: &Scene<F>,
+    pixels: &mut RgbImage,
+    threads: u32
+) where
+    F: Fractal + Sync,
+{
+    let height = pixels.height();
 
     // Divide image into segments
-    let segments: Vec<Vec<PixelData>> = divide_image_into_segments(
-        &mut *pixels.lock().unwrap(),
-        width, height,
-        threads
-    );
-
-    // Let threads process segments
-    let mut thread_handles = vec![];
-
-    for mut segment in segments {
-        let loop_pixels = Arc::clone(&pixels);
-        let loop_theme = color_theme.clone();
-        
-        thread_handles.push(
-            std::thread::spawn(move || {
-                // Process segment
-                for mut pixel_data in &mut segment {
-                    let complex_point = crate::mandelbrot::pixel_to_complex_point(
-                        (pixel_data.x, pixel_data.y),
-                        width, height,
-                        complex_upper_left_corner,
-                        complex_lower_right_corner
-                    );
-                    let iterations = crate::mandelbrot::escape_time(complex_point, limit);
-
-                    pixel_data.pixel = crate::colors::iterations_to_color(
-                        iterations,
-                        limit,
-                        &loop_theme
-                    );
-                }
-
-                // Write processed segment to image
-                for pixel_data in segment {
-                    *loop_pixels.lock().unwrap()
-                        .get_pixel_mut(pixel_data.x, pixel_data.y) = pixel_data.pixel;
-                }
-            })
-        );
-    }
-
-    // Join all threads (wait for them to finish)
-    for handle in thread_handles {
-        handle.join().unwrap();
-    }
+    let width = pixels.width();
+    let segments = divide_image_into_segments(pixels, width, height, threads);
+
+    // Let threads process segments, each writing straight into its own part
+    // of the image, and join them all (wait for them to finish) at the end of
+    // the scope
+    std::thread::scope(|threads| {
+        for mut segment in segments {
+            threads.spawn(move || segment.render(scene, height));
+        }
+    });
 }
 
 /// Splits `pixels` into rows
@@ -350,22 +1748,19 @@ fn divide_image_into_rows(
     pixels: &mut RgbImage,
     width: u32,
     height: u32
-) -> Vec<Vec<PixelData>> {
-    let mut rows: Vec<Vec<PixelData>> = Vec::with_capacity(height as usize);
+) -> Vec<Segment<'_>> {
+    let mut rows: Vec<Segment> = Vec::with_capacity(height as usize);
 
-    for _ in 0..height {
-        rows.push(Vec::with_capacity(width as usize));
+    if width == 0 {
+        return rows;
     }
 
-    // Assign each row its pixels
-    for (_, row) in pixels.enumerate_rows_mut() {
-        for (x, y, pixel) in row {
-            rows[y as usize].push(PixelData {
-                pixel: *pixel,
-                x: x,
-                y: y
-            });
-        }
+    for (y, row) in pixels.chunks_mut(width as usize * 3).enumerate() {
+        rows.push(Segment {
+            start: y * width as usize,
+            width,
+            pixels: row,
+        });
     }
 
     return rows;
@@ -390,117 +1785,535 @@ fn test_divide_image_into_rows() {
     assert_eq!(rows[1].len(), 3);
 
     // Correct pixel coordinates in first row
-    assert_eq!(rows[0][0].x, 0);
-    assert_eq!(rows[0][0].y, 0);
-
-    assert_eq!(rows[0][1].x, 1);
-    assert_eq!(rows[0][1].y, 0);
-
-    assert_eq!(rows[0][2].x, 2);
-    assert_eq!(rows[0][2].y, 0);
+    assert_eq!(rows[0].coords(0), (0, 0));
+    assert_eq!(rows[0].coords(1), (1, 0));
+    assert_eq!(rows[0].coords(2), (2, 0));
 
     // Correct pixel coordinates in second row
-    assert_eq!(rows[1][0].x, 0);
-    assert_eq!(rows[1][0].y, 1);
-
-    assert_eq!(rows[1][1].x, 1);
-    assert_eq!(rows[1][1].y, 1);
-
-    assert_eq!(rows[1][2].x, 2);
-    assert_eq!(rows[1][2].y, 1);
+    assert_eq!(rows[1].coords(0), (0, 1));
+    assert_eq!(rows[1].coords(1), (1, 1));
+    assert_eq!(rows[1].coords(2), (2, 1));
 }
 
-/// Renders a rectangle of the Mandelbrot set with `threads` threads by
-/// breaking up the pixels into rows and tossing the rows into a thread pool
+/// Renders `scene` into `pixels` with the threads of `pool` by
+/// breaking up the pixels into rows and tossing the rows into the pool
 /// for processing
-pub fn render_multithreaded_pooled_rows(
-    limit: u32,
-    complex_upper_left_corner: Complex<f64>,
-    complex_lower_right_corner: Complex<f64>,
-    pixels: Arc<Mutex<RgbImage>>,
-    threads: u32,
-    color_theme: Vec<Rgb<u8>>
-) {
-    let width = pixels.lock().unwrap().width();
-    let height = pixels.lock().unwrap().height();
+pub fn render_multithreaded_pooled_rows<F>(
+    scene: &Scene<F>,
+    pixels: &mut RgbImage,
+    pool: &ThreadPool
+) where
+    F: Fractal + Sync,
+{
+    let width = pixels.width();
+    let height = pixels.height();
+    let panicked_before = pool.panicked_jobs();
 
     // Divide image into rows
-    let rows: Vec<Vec<PixelData>> = divide_image_into_rows(
-        &mut *pixels.lock().unwrap(),
-        width, height
-    );
+    let rows = divide_image_into_rows(pixels, width, height);
+
+    // Let threads process rows, each writing straight into its own row
+    pool.scope(|scope| {
+        for mut row in rows {
+            scope.execute(move || row.render(scene, height));
+        }
+    });
 
-    // Let threads process rows
-    let pool = crate::threadpool::ThreadPool::new(threads as usize);
-
-    for mut row in rows {
-        let loop_pixels = Arc::clone(&pixels);
-        let loop_theme = color_theme.clone();
-
-        pool.execute(move || {
-            // Process row
-            for mut pixel_data in &mut row {
-                let complex_point = crate::mandelbrot::pixel_to_complex_point(
-                    (pixel_data.x, pixel_data.y),
-                    width, height,
-                    complex_upper_left_corner,
-                    complex_lower_right_corner
-                );
-                let iterations = crate::mandelbrot::escape_time(complex_point, limit);
-
-                pixel_data.pixel = crate::colors::iterations_to_color(
-                    iterations,
-                    limit,
-                    &loop_theme
-                );
+    warn_about_panicked_jobs(pool.panicked_jobs() - panicked_before);
+}
+
+/// Reports on stderr that `panicked_jobs` render jobs panicked, if any did
+///
+/// Pixels of jobs that panicked are left untouched, so one bad job doesn't
+/// throw away the rest of a long render.
+fn warn_about_panicked_jobs(panicked_jobs: usize) {
+    if panicked_jobs > 0 {
+        eprintln!(
+            "warning: {} render job(s) panicked, and their pixels were left unrendered",
+            panicked_jobs
+        );
+    }
+}
+
+#[test]
+fn test_render_with_panicking_rows() {
+    // Like `HalfPlane`, but panics on and below the real axis
+    #[derive(Clone)]
+    struct FragileHalfPlane;
+
+    impl Fractal for FragileHalfPlane {
+        fn escape(&self, point: Complex<f64>, limit: u32) -> Escape {
+            if point.im <= 0.0 {
+                panic!("can't handle {}", point);
             }
 
-            // Write processed row to image
-            for pixel_data in row {
-                *loop_pixels.lock().unwrap()
-                    .get_pixel_mut(pixel_data.x, pixel_data.y) = pixel_data.pixel;
+            return HalfPlane.escape(point, limit);
+        }
+    }
+
+    let scene = Scene {
+        fractal: FragileHalfPlane,
+        limit: 10,
+        viewport: Viewport::from_corners(
+            Complex { re: -1.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        coloring: Coloring::new(crate::colors::grayscale()),
+        supersampling: Supersampling::None,
+    };
+    let white = Rgb([255, 255, 255]);
+    let mut pixels = RgbImage::from_pixel(2, 2, white);
+    let pool = ThreadPool::new(2);
+
+    render_multithreaded_pooled_rows(&scene, &mut pixels, &pool);
+
+    // The top row rendered fine...
+    assert_eq!(*pixels.get_pixel(0, 0), white);
+    assert_eq!(*pixels.get_pixel(1, 0), Rgb([0, 0, 0]));
+
+    // ...and the bottom row's panic left it as it was
+    assert_eq!(*pixels.get_pixel(0, 1), white);
+    assert_eq!(*pixels.get_pixel(1, 1), white);
+
+    assert_eq!(pool.panicked_jobs(), 1);
+}
+
+/// Renders `scene` into `pixels` with the threads of `pool` by
+/// tossing all the pixels into the pool for processing
+pub fn render_multithreaded_pooled_pixels<F>(
+    scene: &Scene<F>,
+    pixels: &mut RgbImage,
+    pool: &ThreadPool
+) where
+    F: Fractal + Sync,
+{
+    let width = pixels.width();
+    let height = pixels.height();
+    let panicked_before = pool.panicked_jobs();
+
+    // Let threads process pixels, each writing straight into its own pixel
+    pool.scope(|scope| {
+        for (i, pixel) in pixels.chunks_mut(3).enumerate() {
+            let mut pixel = Segment { start: i, width, pixels: pixel };
+
+            scope.execute(move || pixel.render(scene, height));
+        }
+    });
+
+    warn_about_panicked_jobs(pool.panicked_jobs() - panicked_before);
+}
+
+/// Renders `scene` into `pixels` with the threads of `pool` by
+/// breaking up the pixels into tiles of `tile_size` (width, height) pixels and
+/// tossing the tiles into the pool, in `order`, for processing
+///
+/// Compared to rows, square-ish tiles keep each thread's pixels close
+/// together in the complex plane and in memory, and, in a spiral or Hilbert
+/// `order`, fill in the image in a nicer order for watching it render.
+pub fn render_multithreaded_pooled_tiles<F>(
+    scene: &Scene<F>,
+    pixels: &mut RgbImage,
+    pool: &ThreadPool,
+    tile_size: (u32, u32),
+    order: TileOrder
+) where
+    F: Fractal + Sync,
+{
+    let width = pixels.width();
+    let height = pixels.height();
+    let panicked_before = pool.panicked_jobs();
+
+    // Divide image into tiles
+    let tiles = crate::tiles::divide_image_into_tiles(pixels, tile_size, order);
+
+    // Let threads process tiles, each writing straight into its own tile
+    pool.scope(|scope| {
+        for mut tile in tiles {
+            scope.execute(move || tile.render(scene, width, height));
+        }
+    });
+
+    warn_about_panicked_jobs(pool.panicked_jobs() - panicked_before);
+}
+
+/// Counts, with the threads of `pool`, how many pixels of a `width`×`height`
+/// image of `scene` escape at each iteration count, for coloring it by
+/// `Coloring::equalize`
+///
+/// Each pixel is counted by one point, where it's sampled without
+/// supersampling. This is a whole extra pass over the image, before rendering
+/// it, since a pixel's color depends on how every other pixel escapes.
+pub fn count_histogram<F>(scene: &Scene<F>, width: u32, height: u32, pool: &ThreadPool) -> Histogram
+where
+    F: Fractal + Sync,
+{
+    return Histogram::new(count_iterations(scene, width, height, pool), scene.limit);
+}
+
+/// Returns, row by row, how many iterations each pixel of a `width`×`height`
+/// image of `scene` took to escape, counted with the threads of `pool` at the
+/// point where each pixel is sampled without supersampling
+///
+/// This is what `count_histogram` counts up, and what
+/// `render_adaptive_antialiasing` tells edges by, so both can share one pass.
+pub fn count_iterations<F>(scene: &Scene<F>, width: u32, height: u32, pool: &ThreadPool) -> Vec<u32>
+where
+    F: Fractal + Sync,
+{
+    // There are no rows to split an empty image into
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+
+    let panicked_before = pool.panicked_jobs();
+
+    // Pixels of rows whose jobs panic stay at the limit, so they're left out
+    let mut iterations = vec![scene.limit; width as usize * height as usize];
+
+    pool.scope(|scope| {
+        for (y, row) in iterations.chunks_mut(width as usize).enumerate() {
+            scope.execute(move || {
+                for (x, pixel_iterations) in row.iter_mut().enumerate() {
+                    let sample = crate::antialiasing::single_sample_point((x as u32, y as u32));
+                    let complex_point = scene.viewport.subpixel_to_complex_point(sample, width, height);
+
+                    *pixel_iterations = scene.fractal.escape(complex_point, scene.limit).iterations;
+                }
+            });
+        }
+    });
+
+    warn_about_panicked_jobs(pool.panicked_jobs() - panicked_before);
+
+    return iterations;
+}
+
+#[test]
+fn test_count_histogram() {
+    // The left half of the pixels escape right away, and the rest never do
+    let mut scene = Scene {
+        fractal: HalfPlane,
+        limit: 10,
+        viewport: Viewport::from_corners(
+            Complex { re: -1.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        coloring: Coloring {
+            equalize: true,
+            ..Coloring::new(crate::colors::grayscale())
+        },
+        supersampling: Supersampling::None,
+    };
+    let pool = ThreadPool::new(2);
+    let histogram = count_histogram(&scene, 4, 3, &pool);
+
+    assert_eq!(histogram, Histogram::new(vec![0, 0, 10, 10], 10));
+    assert_eq!(count_histogram(&scene, 0, 3, &pool), Histogram::new(vec![], 10));
+    assert_eq!(count_histogram(&scene, 4, 0, &pool), Histogram::new(vec![], 10));
+
+    // All the escaped pixels escaped at once, so spreading the palette over
+    // them changes nothing
+    let mut unequalized = RgbImage::new(4, 3);
+    let mut equalized = RgbImage::new(4, 3);
+
+    render_singlethreaded(&scene, &mut unequalized);
+    scene.coloring.histogram = Some(histogram);
+    render_singlethreaded(&scene, &mut equalized);
+
+    assert_eq!(equalized, unequalized);
+}
+
+/// A made-up fractal whose points escape after the square of the whole part of
+/// their real part's iterations, so most of them escape early on
+#[cfg(test)]
+struct Squares;
+
+#[cfg(test)]
+impl Fractal for Squares {
+    fn escape(&self, point: Complex<f64>, _limit: u32) -> Escape {
+        let whole = point.re.floor() as u32;
+
+        return Escape { iterations: whole * whole, norm_sqr: 16.0 };
+    }
+}
+
+#[test]
+fn test_count_skewed_histogram() {
+    // Each pixel of a row of 8 gets its own whole real part, so they escape
+    // after 0, 1, 4, 9, ..., 49 iterations, crowded toward the start
+    let scene = Scene {
+        fractal: Squares,
+        limit: 64,
+        viewport: Viewport::from_corners(
+            Complex { re: 0.0, im: 0.5 },
+            Complex { re: 8.0, im: -0.5 }
+        ),
+        coloring: Coloring {
+            equalize: true,
+            ..Coloring::new(crate::colors::grayscale())
+        },
+        supersampling: Supersampling::None,
+    };
+    let pool = ThreadPool::new(2);
+    let histogram = count_histogram(&scene, 8, 1, &pool);
+
+    // The palette starts at the start and ends at the end...
+    assert_eq!(histogram.fraction(0.0), 0.0);
+    assert_eq!(histogram.fraction(64.0), 1.0);
+
+    // ...without ever going back...
+    for n in 0..64 {
+        assert!(histogram.fraction(n as f64) <= histogram.fraction((n + 1) as f64));
+    }
+
+    // ...and spreads the pixels evenly over it, where stretching it over the
+    // iterations would have crowded half of them into its first quarter
+    for x in 0..8u32 {
+        assert_eq!(histogram.fraction((x * x) as f64), x as f64 / 8.0);
+    }
+}
+
+/// Test fractal that escapes right away, keeping every point it was asked about
+#[cfg(test)]
+#[derive(Default)]
+struct Recorder {
+    points: std::sync::Mutex<Vec<(f64, f64)>>,
+}
+
+#[cfg(test)]
+impl Recorder {
+    /// Returns the points asked about so far, sorted, and forgets them
+    fn take_points(&self) -> Vec<(f64, f64)> {
+        let mut points = std::mem::take(&mut *self.points.lock().unwrap());
+
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        return points;
+    }
+}
+
+#[cfg(test)]
+impl Fractal for Recorder {
+    fn escape(&self, point: Complex<f64>, _limit: u32) -> Escape {
+        self.points.lock().unwrap().push((point.re, point.im));
+
+        return Escape { iterations: 0, norm_sqr: 16.0 };
+    }
+}
+
+#[test]
+fn test_count_histogram_samples_rendered_points() {
+    // Equalizing only spreads the palette over the render's pixels if the
+    // counting pass looks at the same point of each pixel as the render
+    let scene = Scene {
+        fractal: Recorder::default(),
+        limit: 10,
+        viewport: Viewport::from_corners(
+            Complex { re: -1.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        coloring: Coloring {
+            equalize: true,
+            ..Coloring::new(crate::colors::grayscale())
+        },
+        supersampling: Supersampling::None,
+    };
+
+    count_histogram(&scene, 4, 2, &ThreadPool::new(2));
+
+    let counted = scene.fractal.take_points();
+
+    render_singlethreaded(&scene, &mut RgbImage::new(4, 2));
+
+    let rendered = scene.fractal.take_points();
+
+    assert_eq!(counted, rendered);
+
+    // Both sample each pixel at its center, not its upper left corner
+    assert_eq!(rendered.len(), 8);
+    assert!(rendered.contains(&(-0.75, 0.5)));
+    assert!(!rendered.contains(&(-1.0, 1.0)));
+}
+
+/// Re-renders, with `supersampling` and the threads of `pool`, only the pixels of
+/// an already-rendered `scene` that differ from a neighbor by more than
+/// `threshold`, returning how many pixels were re-rendered
+///
+/// Jagged edges and aliased filaments only show up where neighboring pixels
+/// have very different colors or escape counts, while most pixels sit in
+/// smooth gradients that supersampling wouldn't change. Supersampling just the
+/// former gets most of the quality of supersampling everything for a fraction
+/// of the cost.
+///
+/// `iterations` is each pixel's escape count, as `count_iterations` returns
+/// them. See `antialiasing::find_edges` for how `threshold` is used.
+pub fn render_adaptive_antialiasing<F>(
+    scene: &Scene<F>,
+    pixels: &mut RgbImage,
+    iterations: &[u32],
+    pool: &ThreadPool,
+    supersampling: Supersampling,
+    threshold: EdgeThreshold
+) -> usize
+where
+    F: Fractal + Clone + Sync,
+{
+    let width = pixels.width();
+    let height = pixels.height();
+    let panicked_before = pool.panicked_jobs();
+
+    // Find all the edges before touching any pixel, so re-rendered pixels
+    // don't change which of their neighbors count as edges
+    let edges = crate::antialiasing::find_edges(pixels, iterations, threshold);
+    let mut edges_by_row: Vec<Vec<usize>> = vec![Vec::new(); height as usize];
+
+    for &(x, y) in &edges {
+        edges_by_row[y as usize].push(x as usize);
+    }
+
+    let mut scene = scene.clone();
+
+    scene.supersampling = supersampling;
+
+    let scene = &scene;
+
+    // Let threads process the edge pixels of a row at a time, each writing
+    // straight into its own row
+    let rows = divide_image_into_rows(pixels, width, height);
+
+    pool.scope(|scope| {
+        for (mut row, row_edges) in rows.into_iter().zip(edges_by_row) {
+            if row_edges.is_empty() {
+                continue;
             }
-        });
+
+            scope.execute(move || {
+                for x in row_edges {
+                    row.render_pixel(x, scene, height);
+                }
+            });
+        }
+    });
+
+    warn_about_panicked_jobs(pool.panicked_jobs() - panicked_before);
+
+    return edges.len();
+}
+
+#[test]
+fn test_render_adaptive_antialiasing() {
+    // The imaginary axis runs through the middle of the middle pixel
+    let mut scene = Scene {
+        fractal: HalfPlane,
+        limit: 10,
+        viewport: Viewport::from_corners(
+            Complex { re: -1.5, im: 0.5 },
+            Complex { re: 1.5, im: -0.5 }
+        ),
+        coloring: Coloring::new(crate::colors::grayscale()),
+        supersampling: Supersampling::None,
+    };
+    let mut pixels = RgbImage::new(3, 1);
+
+    render_singlethreaded(&scene, &mut pixels);
+
+    // Unsampled, the middle pixel's center lands on the axis
+    assert_eq!(*pixels.get_pixel(1, 0), Rgb([0, 0, 0]));
+
+    let pool = ThreadPool::new(2);
+    let iterations = count_iterations(&scene, 3, 1, &pool);
+    let resampled = render_adaptive_antialiasing(
+        &scene,
+        &mut pixels,
+        &iterations,
+        &pool,
+        Supersampling::Grid(4),
+        EdgeThreshold { color: 32, iterations: 4 }
+    );
+
+    // Only the two pixels on either side of the color change were resampled...
+    assert_eq!(resampled, 2);
+
+    // ...which matches supersampling everything
+    let mut supersampled_pixels = RgbImage::new(3, 1);
+
+    scene.supersampling = Supersampling::Grid(4);
+    render_singlethreaded(&scene, &mut supersampled_pixels);
+
+    assert_eq!(pixels, supersampled_pixels);
+}
+
+#[test]
+fn test_single_sample_matches_grid() {
+    // One sample per pixel is a 1×1 grid, so re-rendering edges with a grid
+    // doesn't shift them against their neighbors
+    let mut scene = Scene {
+        fractal: FractalKind::Mandelbrot,
+        limit: 50,
+        viewport: Viewport::from_corners(
+            Complex { re: -2.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        coloring: Coloring::new(crate::colors::fire()),
+        supersampling: Supersampling::None,
+    };
+
+    for &pixel_coords in &[(0, 0), (5, 3), (11, 7), (16, 10)] {
+        let single = scene.pixel_color(pixel_coords, 17, 11);
+
+        scene.supersampling = Supersampling::Grid(1);
+        assert_eq!(scene.pixel_color(pixel_coords, 17, 11), single);
+        scene.supersampling = Supersampling::None;
     }
 }
 
-/// Renders a rectangle of the Mandelbrot set with `threads` threads by
-/// tossing all the pixels into a thread pool for processing
-pub fn render_multithreaded_pooled_pixels(
-    limit: u32,
-    complex_upper_left_corner: Complex<f64>,
-    complex_lower_right_corner: Complex<f64>,
-    pixels: Arc<Mutex<RgbImage>>,
-    threads: u32,
-    color_theme: Vec<Rgb<u8>>
-) {
-    let width = pixels.lock().unwrap().width();
-    let height = pixels.lock().unwrap().height();
-
-    // Let threads process pixels
-    let pool = crate::threadpool::ThreadPool::new(threads as usize);
-
-    for (x, y, _) in pixels.lock().unwrap().enumerate_pixels_mut() {
-        let loop_pixels = Arc::clone(&pixels);
-        let loop_theme = color_theme.clone();
-
-        pool.execute(move || {
-            // Process pixel
-            let complex_point = crate::mandelbrot::pixel_to_complex_point(
-                (x, y),
-                width, height,
-                complex_upper_left_corner,
-                complex_lower_right_corner
-            );
-            let iterations = crate::mandelbrot::escape_time(complex_point, limit);
-
-            // Write processed pixel to image
-            *loop_pixels.lock().unwrap()
-                .get_pixel_mut(x, y) = crate::colors::iterations_to_color(
-                    iterations,
-                    limit,
-                    &loop_theme
-                );
-        });
+#[test]
+fn test_strategies_agree() {
+    let scene = Scene {
+        fractal: FractalKind::Mandelbrot,
+        limit: 50,
+        viewport: Viewport::from_corners(
+            Complex { re: -2.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        coloring: Coloring::new(crate::colors::fire()),
+        supersampling: Supersampling::None,
+    };
+    let pool = ThreadPool::new(3);
+    let mut singlethreaded = RgbImage::new(17, 11);
+    let mut segments = RgbImage::new(17, 11);
+    let mut rows = RgbImage::new(17, 11);
+    let mut pixels = RgbImage::new(17, 11);
+
+    render_singlethreaded(&scene, &mut singlethreaded);
+    render_multithreaded_preallocated_segments(&scene, &mut segments, 4);
+    render_multithreaded_pooled_rows(&scene, &mut rows, &pool);
+    render_multithreaded_pooled_pixels(&scene, &mut pixels, &pool);
+
+    assert!(segments == singlethreaded);
+    assert!(rows == singlethreaded);
+    assert!(pixels == singlethreaded);
+
+    for &order in &[TileOrder::RowMajor, TileOrder::Spiral, TileOrder::Hilbert] {
+        let mut tiles = RgbImage::new(17, 11);
+
+        render_multithreaded_pooled_tiles(&scene, &mut tiles, &pool, (4, 3), order);
+
+        assert!(tiles == singlethreaded);
+    }
+
+    // ...and so do strategies picked at runtime
+    for strategy in &[
+        RenderStrategy::Singlethreaded,
+        RenderStrategy::PooledPixels,
+        RenderStrategy::PreallocatedSegments,
+        RenderStrategy::PooledRows,
+        RenderStrategy::PooledTiles { tile_size: (5, 5), order: TileOrder::Hilbert },
+    ] {
+        let mut strategy_pixels = RgbImage::new(17, 11);
+
+        strategy.render(&scene, &mut strategy_pixels, &pool);
+
+        assert!(strategy_pixels == singlethreaded, "{} disagrees", strategy);
     }
 }
@@ -3,32 +3,192 @@ This file contains code from Programming Rust by Jim Blandy and Jason Orendorff
 (O'Reilly), copyright 2018 Jim Blandy and Jason Orendorff, 978-1-491-92728-1.
 */
 
+use std::fmt;
+
 use num_complex::Complex;
 
+use crate::fractal::{Escape, Fractal, Orbit};
+
+/// The Mandelbrot set, where each point is `c` and `z` starts at 0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mandelbrot;
+
+impl Fractal for Mandelbrot {
+    fn escape(&self, point: Complex<f64>, limit: u32) -> Escape {
+        return iterate(Complex { re: 0.0, im: 0.0 }, point, limit);
+    }
+
+    fn orbit(&self, point: Complex<f64>, limit: u32) -> (Escape, Orbit) {
+        return orbit(Complex { re: 0.0, im: 0.0 }, point, limit);
+    }
+}
+
+/// The Julia set of the constant `c`, where each point is the starting `z`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Julia {
+    pub c: Complex<f64>,
+}
+
+impl Fractal for Julia {
+    fn escape(&self, point: Complex<f64>, limit: u32) -> Escape {
+        return iterate(point, self.c, limit);
+    }
+
+    fn orbit(&self, point: Complex<f64>, limit: u32) -> (Escape, Orbit) {
+        return orbit(point, self.c, limit);
+    }
+}
+
+/// The kind of fractal to render, which decides how each point of the complex
+/// plane is iterated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FractalKind {
+    /// The Mandelbrot set, where each point is `c` and `z` starts at 0
+    Mandelbrot,
+    /// The Julia set of the contained constant `c`, where each point is the
+    /// starting `z`
+    Julia(Complex<f64>),
+}
+
+impl Fractal for FractalKind {
+    fn escape(&self, point: Complex<f64>, limit: u32) -> Escape {
+        match *self {
+            FractalKind::Mandelbrot => Mandelbrot.escape(point, limit),
+            FractalKind::Julia(c) => Julia { c }.escape(point, limit),
+        }
+    }
+
+    fn orbit(&self, point: Complex<f64>, limit: u32) -> (Escape, Orbit) {
+        match *self {
+            FractalKind::Mandelbrot => Mandelbrot.orbit(point, limit),
+            FractalKind::Julia(c) => Julia { c }.orbit(point, limit),
+        }
+    }
+}
+
+/// Prints the fractal the way `parse_fractal` parses it, like `mandelbrot` or
+/// `julia:-0.8,0.156`
+impl fmt::Display for FractalKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FractalKind::Mandelbrot => write!(f, "mandelbrot"),
+            FractalKind::Julia(c) => write!(f, "julia:{},{}", c.re, c.im),
+        }
+    }
+}
+
 /// Returns the number of iterations, up to the given `limit`, it took for `c`
 /// to escape the Mandelbrot set
 ///
 /// If the return value is `limit`, `c` did not escape within `limit` iterations,
 /// indicating that `c` is probably in the set.
 pub fn escape_time(c: Complex<f64>, limit: u32) -> u32 {
-    let mut z = Complex { re: 0.0, im: 0.0 };
+    return iterate(Complex { re: 0.0, im: 0.0 }, c, limit).iterations;
+}
+
+/// Returns the number of iterations, up to the given `limit`, it took for `z0`
+/// to escape the Julia set of the constant `c`
+///
+/// If the return value is `limit`, `z0` did not escape within `limit`
+/// iterations, indicating that `z0` is probably in the set.
+pub fn julia_escape_time(z0: Complex<f64>, c: Complex<f64>, limit: u32) -> u32 {
+    return iterate(z0, c, limit).iterations;
+}
+
+/// Returns how `z`, starting at `z0`, escaped when computing `z = z * z + c`
+/// up to the given `limit` times
+fn iterate(z0: Complex<f64>, c: Complex<f64>, limit: u32) -> Escape {
+    return iterate_visiting(z0, c, limit, |_| {});
+}
+
+/// Iterates like `iterate`, handing every `z` it goes through, up to and
+/// including the one that escapes, to `visit`
+fn iterate_visiting<V>(z0: Complex<f64>, c: Complex<f64>, limit: u32, mut visit: V) -> Escape
+where
+    V: FnMut(Complex<f64>),
+{
+    let mut z = z0;
     let mut i: u32 = 0;
 
     while i < limit {
         z = z * z + c;
+        visit(z);
 
         // A classic shortcut this code uses is the wisdom that, if `z` ever
         // leaves a circle of radius 2 centered on the origin, it will fly out
         // to infinity eventually, and thus prove itself to be outside the set.
         if z.norm_sqr() > 4.0 {
-            return i;
+            return Escape { iterations: i, norm_sqr: z.norm_sqr() };
         }
 
         i += 1;
     }
 
     // Down here, i == limit
-    return limit;
+    return Escape { iterations: limit, norm_sqr: z.norm_sqr() };
+}
+
+/// Returns how `z`, starting at `z0`, escaped, like `iterate`, along with the
+/// orbit of every `z` it went through, up to and including the one that
+/// escapes
+fn orbit(z0: Complex<f64>, c: Complex<f64>, limit: u32) -> (Escape, Orbit) {
+    let mut orbit = Orbit::new();
+    let escape = iterate_visiting(z0, c, limit, |z| orbit.visit(z));
+
+    return (escape, orbit);
+}
+
+#[test]
+fn test_orbit() {
+    let origin = Complex { re: 0.0, im: 0.0 };
+
+    // c = -1 goes back and forth between -1 and 0 forever...
+    let c = Complex { re: -1.0, im: 0.0 };
+    let (escape, orbit) = FractalKind::Mandelbrot.orbit(c, 4);
+
+    assert_eq!(escape, FractalKind::Mandelbrot.escape(c, 4));
+    assert_eq!(orbit.length, 4);
+    assert_eq!(orbit.period, Some(2));
+
+    // ...and c = 1 goes 1, 2, 5, and that's out
+    let c = Complex { re: 1.0, im: 0.0 };
+    let (escape, orbit) = Mandelbrot.orbit(c, 10);
+
+    assert_eq!(escape, Mandelbrot.escape(c, 10));
+    assert_eq!(orbit.length, 3);
+    assert_eq!(orbit.period, None);
+    assert_eq!(orbit.average_angle(), Some(0.0));
+
+    let (escape, orbit) = Julia { c: origin }.orbit(c, 10);
+
+    assert_eq!(escape, Julia { c: origin }.escape(c, 10));
+    assert_eq!(orbit.length, 10);
+    assert_eq!(orbit.period, Some(1));
+
+    // The Mandelbrot set's period 3 bulb, where the rabbit Julia set comes from
+    let rabbit = Complex { re: -0.1226, im: 0.7449 };
+
+    assert_eq!(Mandelbrot.orbit(rabbit, 1000).1.period, Some(3));
+}
+
+#[test]
+fn test_julia_escape_time() {
+    let origin = Complex { re: 0.0, im: 0.0 };
+
+    // With c = 0, points inside the unit circle never escape...
+    assert_eq!(julia_escape_time(Complex { re: 0.5, im: 0.5 }, origin, 100), 100);
+    // ...and points outside the radius-2 circle escape right away
+    assert_eq!(julia_escape_time(Complex { re: 3.0, im: 0.0 }, origin, 100), 0);
+
+    // The Mandelbrot set is the Julia iteration started at the origin
+    let c = Complex { re: -0.75, im: 0.1 };
+    assert_eq!(escape_time(c, 200), julia_escape_time(origin, c, 200));
+
+    assert_eq!(FractalKind::Mandelbrot.escape(c, 200).iterations, escape_time(c, 200));
+    assert_eq!(
+        FractalKind::Julia(c).escape(Complex { re: 0.3, im: -0.2 }, 200).iterations,
+        julia_escape_time(Complex { re: 0.3, im: -0.2 }, c, 200)
+    );
 }
 
 /// Returns the point on the complex plane corresponding to the given image
@@ -46,6 +206,23 @@ pub fn pixel_to_complex_point(
     height: u32,
     complex_upper_left_bound: Complex<f64>,
     complex_lower_right_bound: Complex<f64>
+) -> Complex<f64> {
+    return subpixel_to_complex_point(
+        (pixel_coords.0 as f64, pixel_coords.1 as f64),
+        width, height,
+        complex_upper_left_bound,
+        complex_lower_right_bound
+    );
+}
+
+/// Like `pixel_to_complex_point`, but for fractional pixel coordinates, such
+/// as the points sampled within a pixel when supersampling
+pub fn subpixel_to_complex_point(
+    pixel_coords: (f64, f64),
+    width: u32,
+    height: u32,
+    complex_upper_left_bound: Complex<f64>,
+    complex_lower_right_bound: Complex<f64>
 ) -> Complex<f64> {
     let (pixel_x, pixel_y) = (pixel_coords.0, pixel_coords.1);
     let real_scale = complex_lower_right_bound.re - complex_upper_left_bound.re;
@@ -56,8 +233,8 @@ pub fn pixel_to_complex_point(
     // graphics code tradition), the imaginary component DECREASES, and vice
     // versa...
     return Complex {
-        re: complex_upper_left_bound.re + (pixel_x as f64 * real_scale as f64 / width as f64),
-        im: complex_upper_left_bound.im - (pixel_y as f64 * imag_scale as f64 / height as f64)
+        re: complex_upper_left_bound.re + (pixel_x * real_scale / width as f64),
+        im: complex_upper_left_bound.im - (pixel_y * imag_scale / height as f64)
     }
 }
 
@@ -73,3 +250,16 @@ fn test_pixel_to_complex_point() {
         Complex { re: -0.5, im: -0.5 }
     );
 }
+
+#[test]
+fn test_subpixel_to_complex_point() {
+    assert_eq!(
+        subpixel_to_complex_point(
+            (25.5, 75.5),
+            100, 100,
+            Complex { re: -1.0, im: 1.0 },
+            Complex { re: 1.0, im: -1.0 }
+        ),
+        Complex { re: -0.49, im: -0.51 }
+    );
+}
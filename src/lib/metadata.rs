@@ -0,0 +1,190 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use image::RgbImage;
+
+use crate::scene_file::{SceneFile, SceneFormat};
+
+/// The keyword of the iTXt chunk holding the whole scene, as a TOML scene
+/// file, which is what an image is re-rendered from
+pub const SCENE_KEYWORD: &str = "Scene";
+
+/// Returns the chunks describing `scene` at a glance, as (keyword, text), for
+/// image viewers and tools like `exiftool` to show
+pub fn text_chunks(scene: &SceneFile) -> Vec<(&'static str, String)> {
+    let viewport = &scene.viewport;
+    let mut chunks = vec![
+        ("Software", "threadpool_fractal".to_string()),
+        ("Fractal", scene.fractal.clone()),
+    ];
+
+    if let Some([re, im]) = viewport.center {
+        chunks.push(("Center", format!("{},{}", re, im)));
+    }
+
+    if let Some(radius) = viewport.radius {
+        chunks.push(("Radius", radius.to_string()));
+    }
+
+    chunks.push(("Rotation", viewport.rotation.to_string()));
+    chunks.push(("Iterations", scene.iterations.to_string()));
+    chunks.push(("Palette", scene.palette.name().to_string()));
+
+    return chunks;
+}
+
+/// Writes `pixels` as a PNG to `writer`, with `scene` embedded in its text
+/// chunks
+pub fn encode_png<W: Write>(
+    pixels: &RgbImage,
+    writer: W,
+    scene: &SceneFile
+) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(writer, pixels.width(), pixels.height());
+
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    // tEXt chunks can only hold Latin-1, so anything else goes in an iTXt
+    // chunk, which holds UTF-8
+    for (keyword, text) in text_chunks(scene) {
+        if text.is_ascii() {
+            encoder.add_text_chunk(keyword.to_string(), text)?;
+        }
+        else {
+            encoder.add_itxt_chunk(keyword.to_string(), text)?;
+        }
+    }
+
+    encoder.add_itxt_chunk(SCENE_KEYWORD.to_string(), scene.format(SceneFormat::Toml))?;
+
+    let mut writer = encoder.write_header()?;
+
+    writer.write_image_data(pixels.as_raw())?;
+
+    return writer.finish();
+}
+
+/// Writes `pixels` as a PNG to the file at `path`, with `scene` embedded in
+/// its text chunks
+pub fn save_png(pixels: &RgbImage, path: &str, scene: &SceneFile) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|error| format!("couldn't write image file `{}`: {}", path, error))?;
+
+    return encode_png(pixels, BufWriter::new(file), scene)
+        .map_err(|error| format!("couldn't write image file `{}`: {}", path, error));
+}
+
+/// Returns the scene embedded in a PNG read from `reader`
+pub fn decode_scene<R: Read>(reader: R) -> Result<SceneFile, String> {
+    // Text chunks before the image data are read along with the header, and
+    // `encode_png` writes them all there
+    let reader = png::Decoder::new(reader).read_info().map_err(|error| error.to_string())?;
+    let chunk = reader.info().utf8_text.iter()
+        .find(|chunk| chunk.keyword == SCENE_KEYWORD)
+        .ok_or_else(|| "no scene embedded in it".to_string())?;
+    let text = chunk.get_text().map_err(|error| error.to_string())?;
+
+    return SceneFile::parse(&text, SceneFormat::Toml);
+}
+
+/// Returns the scene embedded in the PNG at `path`, which must have been
+/// rendered by this program
+pub fn read_scene(path: &str) -> Result<SceneFile, String> {
+    let file = File::open(path)
+        .map_err(|error| format!("couldn't read image file `{}`: {}", path, error))?;
+
+    return decode_scene(BufReader::new(file))
+        .map_err(|error| format!("couldn't re-render `{}`: {}", path, error));
+}
+
+/// Returns whether `path` names a PNG, going by its extension, which is what
+/// `image` goes by to pick a format, too
+pub fn is_png(path: &str) -> bool {
+    return std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+}
+
+#[test]
+fn test_png_metadata() {
+    let mut pixels = RgbImage::new(3, 2);
+
+    pixels.put_pixel(1, 1, image::Rgb([10, 20, 30]));
+
+    let scene = SceneFile {
+        fractal: "julia:-0.8,0.156".to_string(),
+        palette: crate::scene_file::PaletteEntry::Name("émeraude".to_string()),
+        ..SceneFile::default()
+    };
+
+    let mut png = Vec::new();
+
+    encode_png(&pixels, &mut png, &scene).unwrap();
+
+    // The scene comes back out whole...
+    assert_eq!(decode_scene(png.as_slice()), Ok(scene.clone()));
+
+    // ...the pixels are untouched...
+    let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+
+    assert_eq!(decoded.to_rgb8(), pixels);
+
+    // ...and the parts of it worth a glance are there in plain text
+    let decoder = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let info = decoder.info();
+    let plain: Vec<(&str, &str)> = info.uncompressed_latin1_text.iter()
+        .map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+        .collect();
+
+    assert!(plain.contains(&("Fractal", "julia:-0.8,0.156")));
+    assert!(plain.contains(&("Iterations", "250")));
+    assert!(info.utf8_text.iter().any(|chunk| chunk.keyword == "Palette"));
+}
+
+#[test]
+fn test_png_palette_without_file() {
+    // Images rendered with a palette file can be re-rendered where it isn't
+    let path = std::env::temp_dir().join("threadpool_fractal_test_png_palette.gpl");
+    let path = path.to_str().unwrap();
+
+    std::fs::write(path, "GIMP Palette\n255 136 0\n0 128 128\n16 32 48\n").unwrap();
+
+    let mut args = crate::Args::default();
+
+    args.coloring.palette = crate::parse_palette(path).unwrap();
+    args.palette_name = path.to_string();
+
+    let mut png = Vec::new();
+
+    encode_png(&RgbImage::new(2, 2), &mut png, &SceneFile::from_args(&args)).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let mut applied = crate::Args::default();
+
+    decode_scene(png.as_slice()).unwrap().apply_to(&mut applied).unwrap();
+
+    assert_eq!(applied.coloring.palette, args.coloring.palette);
+    assert_eq!(applied.palette_name, path);
+}
+
+#[test]
+fn test_png_without_scene() {
+    let mut png = Vec::new();
+
+    image::DynamicImage::ImageRgb8(RgbImage::new(2, 2))
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    assert_eq!(decode_scene(png.as_slice()), Err("no scene embedded in it".to_string()));
+    assert!(decode_scene(&b"not a png"[..]).is_err());
+}
+
+#[test]
+fn test_is_png() {
+    assert!(is_png("frac.png"));
+    assert!(is_png("FRAC.PNG"));
+    assert!(!is_png("frac.jpg"));
+    assert!(!is_png("frac"));
+}
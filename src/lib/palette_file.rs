@@ -0,0 +1,546 @@
+use std::path::{Path, PathBuf};
+
+use image::Rgb;
+use serde::Deserialize;
+
+use crate::colors::{Interpolation, Palette};
+
+/// The directory palette files are looked for in, so they can be picked by
+/// name, like the built-in color themes
+///
+/// This is relative to the current directory, so picking palettes by name
+/// only finds these ones when running from the repository's root.
+pub const PALETTE_DIR: &str = "palettes";
+
+/// The formats a palette file can be written in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PaletteFormat {
+    /// One hex color per line, like `#ff8800`, optionally followed by its
+    /// position, like `#ff8800 0.25`, where lines starting with `;`, or with
+    /// `#` and then a space, are comments
+    Hex,
+    /// A GIMP palette, with a `GIMP Palette` header line and then one
+    /// `<red> <green> <blue> [name]` color per line
+    Gpl,
+    /// An object with a `colors` array of hex colors, each of which can
+    /// instead be a stop like `{"color": "#ff8800", "position": 0.25}`, and
+    /// optionally the `interpolation` to blend them with, like `"oklab"`
+    Json,
+}
+
+impl PaletteFormat {
+    /// Returns the format of the file at `path`, going by its extension: GIMP
+    /// for `.gpl`, JSON for `.json`, and hex colors for anything else
+    pub fn of(path: &Path) -> PaletteFormat {
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("gpl") => PaletteFormat::Gpl,
+            Some("json") => PaletteFormat::Json,
+            _ => PaletteFormat::Hex
+        }
+    }
+}
+
+/// Reads the palette in the file at `path`, in the format its extension says
+pub fn load(path: &Path) -> Result<Palette, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("couldn't read palette file `{}`: {}", path.display(), error))?;
+
+    return parse(&contents, PaletteFormat::of(path))
+        .map_err(|error| format!("invalid palette file `{}`: {}", path.display(), error));
+}
+
+/// Parses a palette written in `format`
+pub fn parse(contents: &str, format: PaletteFormat) -> Result<Palette, String> {
+    let palette = match format {
+        PaletteFormat::Hex => parse_hex_list(contents)?,
+        PaletteFormat::Gpl => parse_gpl(contents)?,
+        PaletteFormat::Json => parse_json(contents)?,
+    };
+
+    if palette.colors.len() < 2 {
+        return Err(format!("expected at least 2 colors, but got {}", palette.colors.len()));
+    }
+
+    return Ok(palette);
+}
+
+/// Returns the palette files in `dir`, by name (their filename without its
+/// extension), sorted by name
+///
+/// Returns nothing if `dir` doesn't exist.
+pub fn find_palette_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new()
+    };
+    let mut files: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+
+            return Some((name, path));
+        })
+        .collect();
+
+    files.sort();
+
+    return files;
+}
+
+/// Parses a hex color, like `#ff8800` or `ff8800`
+pub fn parse_hex_color(s: &str) -> Option<Rgb<u8>> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    return Some(Rgb([channel(0)?, channel(2)?, channel(4)?]));
+}
+
+/// Writes `color` the way `parse_hex_color` reads it, like `#ff8800`
+pub fn format_hex_color(color: &Rgb<u8>) -> String {
+    return format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2]);
+}
+
+#[test]
+fn test_parse_hex_color() {
+    assert_eq!(parse_hex_color("#ff8800"), Some(Rgb([255, 136, 0])));
+    assert_eq!(parse_hex_color("10A0fF"), Some(Rgb([16, 160, 255])));
+    assert_eq!(parse_hex_color("#ff880"), None);
+    assert_eq!(parse_hex_color("#gg8800"), None);
+    assert_eq!(parse_hex_color("#+f8800"), None);
+
+    assert_eq!(format_hex_color(&Rgb([255, 136, 0])), "#ff8800");
+    assert_eq!(parse_hex_color(&format_hex_color(&Rgb([1, 2, 3]))), Some(Rgb([1, 2, 3])));
+}
+
+/// Parses one hex color per line, optionally followed by its position, with
+/// anything after that (like its name) ignored
+fn parse_hex_list(contents: &str) -> Result<Palette, String> {
+    let mut colors = Vec::new();
+    let mut positions = Vec::new();
+    let mut last_position: Option<f64> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        let first_word = match words.next() {
+            Some(word) => word,
+            None => continue
+        };
+
+        if is_hex_comment(line) {
+            continue;
+        }
+
+        let color = parse_hex_color(first_word).ok_or_else(|| format!(
+            "line {}: `{}` isn't a hex color, like #ff8800",
+            number + 1,
+            first_word
+        ))?;
+
+        // Positions are numbers, and anything else is the color's name
+        let position = match words.next() {
+            Some(word) if word.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) => {
+                match word.parse::<f64>() {
+                    Ok(position) if (0.0..=1.0).contains(&position) => Some(position),
+                    _ => return Err(format!(
+                        "line {}: `{}` isn't a stop position between 0 and 1",
+                        number + 1,
+                        word
+                    ))
+                }
+            }
+            _ => None
+        };
+
+        if let Some(position) = position {
+            if last_position.is_some_and(|previous| position < previous) {
+                return Err(format!(
+                    "line {}: stop position {} comes before the one above it",
+                    number + 1,
+                    position
+                ));
+            }
+
+            last_position = Some(position);
+        }
+
+        colors.push(color);
+        positions.push(position);
+    }
+
+    return Ok(Palette {
+        colors,
+        positions: fill_positions(&positions)?,
+        interpolation: Interpolation::Srgb
+    });
+}
+
+/// Returns whether `line` of a hex palette is a comment, which starts with `;`,
+/// or with `#` and then a space (or nothing)
+///
+/// Anything else is a color, so that a mistyped one like `#ff880` is an error
+/// instead of a comment, and a comment word made of hex digits, like `#decade`,
+/// can't be taken for a color.
+fn is_hex_comment(line: &str) -> bool {
+    let line = line.trim_start();
+
+    if line.starts_with(';') {
+        return true;
+    }
+
+    return match line.strip_prefix('#') {
+        Some(rest) => rest.is_empty() || rest.starts_with(char::is_whitespace),
+        None => false
+    };
+}
+
+#[test]
+fn test_parse_hex_list() {
+    assert!(is_hex_comment("# Sunset"));
+    assert!(is_hex_comment("#"));
+    assert!(is_hex_comment("  ; Sunset palette"));
+    assert!(is_hex_comment(";decade"));
+    assert!(!is_hex_comment("#Sunset"));
+    assert!(!is_hex_comment("#decade"));
+    assert!(!is_hex_comment("#ff880"));
+    assert!(!is_hex_comment("#ff8800 orange"));
+
+    // Comments made of hex letters stay comments
+    assert_eq!(
+        parse_hex_list("# decade\n;facade\n#000000\n#ffffff\n"),
+        Ok(Palette::new(vec![Rgb([0, 0, 0]), Rgb([255, 255, 255])]))
+    );
+
+    // Mistyped colors and positions are errors, not comments or names
+    assert_eq!(
+        parse_hex_list("#ff8800\n#ff880\n").map(|_| ()),
+        Err("line 2: `#ff880` isn't a hex color, like #ff8800".to_string())
+    );
+    assert_eq!(
+        parse_hex_list("#ff8800\n#bead\n").map(|_| ()),
+        Err("line 2: `#bead` isn't a hex color, like #ff8800".to_string())
+    );
+    assert_eq!(
+        parse_hex_list("# Sunset\n#ff8800 0.5x\n").map(|_| ()),
+        Err("line 2: `0.5x` isn't a stop position between 0 and 1".to_string())
+    );
+    assert_eq!(
+        parse_hex_list("#ff8800\n#008080 1.5\n").map(|_| ()),
+        Err("line 2: `1.5` isn't a stop position between 0 and 1".to_string())
+    );
+    assert_eq!(
+        parse_hex_list("#ff8800 0.5\n\n#008080 0.25\n").map(|_| ()),
+        Err("line 3: stop position 0.25 comes before the one above it".to_string())
+    );
+}
+
+/// Parses a GIMP palette
+fn parse_gpl(contents: &str) -> Result<Palette, String> {
+    let mut lines = contents.lines().enumerate();
+    let mut colors = Vec::new();
+
+    if lines.next().map(|(_, header)| header.trim()) != Some("GIMP Palette") {
+        return Err("expected a `GIMP Palette` header line".to_string());
+    }
+
+    for (number, line) in lines {
+        let line = line.trim();
+
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:") {
+            continue;
+        }
+
+        let channels: Vec<u8> = line.split_whitespace()
+            .take(3)
+            .map_while(|channel| channel.parse().ok())
+            .collect();
+
+        if channels.len() != 3 {
+            return Err(format!(
+                "line {}: `{}` isn't a color, like `255 136 0 Orange`",
+                number + 1,
+                line
+            ));
+        }
+
+        colors.push(Rgb([channels[0], channels[1], channels[2]]));
+    }
+
+    return Ok(Palette::new(colors));
+}
+
+/// A palette file in JSON
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonPalette {
+    colors: Vec<JsonStop>,
+    interpolation: Option<String>,
+}
+
+/// A color of a JSON palette file, which can give its position or not
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonStop {
+    Color(String),
+    Stop { color: String, position: Option<f64> },
+}
+
+/// Parses a JSON palette
+fn parse_json(contents: &str) -> Result<Palette, String> {
+    let json: JsonPalette = serde_json::from_str(contents).map_err(|error| error.to_string())?;
+    let mut colors = Vec::new();
+    let mut positions = Vec::new();
+
+    for stop in json.colors {
+        let (color, position) = match stop {
+            JsonStop::Color(color) => (color, None),
+            JsonStop::Stop { color, position } => (color, position),
+        };
+
+        colors.push(
+            parse_hex_color(&color)
+                .ok_or_else(|| format!("`{}` isn't a hex color, like #ff8800", color))?
+        );
+        positions.push(position);
+    }
+
+    let interpolation = match json.interpolation {
+        Some(interpolation) => crate::require(
+            crate::parse_interpolation(&format!("interpolation:{}", interpolation)),
+            "interpolation", &interpolation, crate::INTERPOLATION_NAMES
+        )?,
+        None => Interpolation::Srgb
+    };
+
+    return Ok(Palette { colors, positions: fill_positions(&positions)?, interpolation });
+}
+
+/// Returns the palette with the hex `colors`, at `positions` if there are any,
+/// blended with `interpolation`, checked like the colors of a palette file
+pub fn from_hex_colors(
+    colors: &[String],
+    positions: Option<&[f64]>,
+    interpolation: Interpolation
+) -> Result<Palette, String> {
+    let colors = colors.iter()
+        .map(|color| {
+            parse_hex_color(color).ok_or_else(|| format!("`{}` isn't a hex color, like #ff8800", color))
+        })
+        .collect::<Result<Vec<Rgb<u8>>, String>>()?;
+
+    if colors.len() < 2 {
+        return Err(format!("expected at least 2 colors, but got {}", colors.len()));
+    }
+
+    let positions = match positions {
+        Some(positions) if positions.len() != colors.len() => {
+            return Err(format!(
+                "expected a position for each of the {} colors, but got {}",
+                colors.len(),
+                positions.len()
+            ));
+        }
+        Some(positions) => {
+            let positions: Vec<Option<f64>> = positions.iter().copied().map(Some).collect();
+
+            fill_positions(&positions)?
+        }
+        None => None
+    };
+
+    return Ok(Palette { colors, positions, interpolation });
+}
+
+#[test]
+fn test_from_hex_colors() {
+    let colors = vec!["#ff8800".to_string(), "#008080".to_string()];
+
+    assert_eq!(
+        from_hex_colors(&colors, Some(&[0.25, 1.0]), Interpolation::Oklab),
+        Ok(Palette {
+            colors: vec![Rgb([255, 136, 0]), Rgb([0, 128, 128])],
+            positions: Some(vec![0.25, 1.0]),
+            interpolation: Interpolation::Oklab
+        })
+    );
+    assert_eq!(
+        from_hex_colors(&colors[..1], None, Interpolation::Srgb),
+        Err("expected at least 2 colors, but got 1".to_string())
+    );
+    assert_eq!(
+        from_hex_colors(&colors, Some(&[0.5]), Interpolation::Srgb),
+        Err("expected a position for each of the 2 colors, but got 1".to_string())
+    );
+    assert_eq!(
+        from_hex_colors(&colors, Some(&[0.5, 0.25]), Interpolation::Srgb),
+        Err("stop position 0.25 comes before the one above it".to_string())
+    );
+    assert_eq!(
+        from_hex_colors(&["#ff8800".to_string(), "teal".to_string()], None, Interpolation::Srgb),
+        Err("`teal` isn't a hex color, like #ff8800".to_string())
+    );
+}
+
+/// Returns the positions of a palette's stops, with the ones left out filled
+/// in, or `None` if they're all left out
+///
+/// Like the stops of a CSS gradient, the first stop defaults to 0, the last to
+/// 1, and the ones in between to evenly spaced between the stops around them.
+fn fill_positions(positions: &[Option<f64>]) -> Result<Option<Vec<f64>>, String> {
+    if positions.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+
+    let last = positions.len() - 1;
+    let mut filled: Vec<f64> = Vec::with_capacity(positions.len());
+
+    for (i, position) in positions.iter().enumerate() {
+        let position = match *position {
+            Some(position) => position,
+            None if i == 0 => 0.0,
+            None if i == last => 1.0,
+            None => {
+                // Spread out evenly from the stop before to the next given one
+                let (next, next_position) = positions.iter().enumerate()
+                    .skip(i + 1)
+                    .find_map(|(next, position)| position.map(|position| (next, position)))
+                    .unwrap_or((last, 1.0));
+                let previous_position = filled[i - 1];
+
+                previous_position
+                    + (next_position - previous_position) / (next - i + 1) as f64
+            }
+        };
+
+        if !(0.0..=1.0).contains(&position) {
+            return Err(format!("stop position {} isn't between 0 and 1", position));
+        }
+
+        if filled.last().is_some_and(|&previous| position < previous) {
+            return Err(format!("stop position {} comes before the one above it", position));
+        }
+
+        filled.push(position);
+    }
+
+    return Ok(Some(filled));
+}
+
+#[test]
+fn test_fill_positions() {
+    assert_eq!(fill_positions(&[None, None, None]), Ok(None));
+    assert_eq!(
+        fill_positions(&[None, Some(0.8), None]),
+        Ok(Some(vec![0.0, 0.8, 1.0]))
+    );
+    assert_eq!(
+        fill_positions(&[Some(0.2), None, None, Some(0.8), None]),
+        Ok(Some(vec![0.2, 0.4, 0.6000000000000001, 0.8, 1.0]))
+    );
+    assert_eq!(
+        fill_positions(&[None, Some(1.5)]),
+        Err("stop position 1.5 isn't between 0 and 1".to_string())
+    );
+    assert_eq!(
+        fill_positions(&[Some(0.5), Some(0.25)]),
+        Err("stop position 0.25 comes before the one above it".to_string())
+    );
+}
+
+#[test]
+fn test_parse_palette_files() {
+    let orange = Rgb([255, 136, 0]);
+    let teal = Rgb([0, 128, 128]);
+
+    assert_eq!(
+        parse("# Sunset\n#ff8800 orange\n\n008080\n", PaletteFormat::Hex),
+        Ok(Palette::new(vec![orange, teal]))
+    );
+    assert_eq!(
+        parse("#ff8800\n#008080 0.75 teal\n#008080\n", PaletteFormat::Hex),
+        Ok(Palette {
+            colors: vec![orange, teal, teal],
+            positions: Some(vec![0.0, 0.75, 1.0]),
+            interpolation: Interpolation::Srgb
+        })
+    );
+    assert_eq!(
+        parse(
+            "GIMP Palette\nName: Sunset\nColumns: 2\n# comment\n255 136   0\tOrange\n  0 128 128\n",
+            PaletteFormat::Gpl
+        ),
+        Ok(Palette::new(vec![orange, teal]))
+    );
+    assert_eq!(
+        parse(
+            r##"{"colors": ["#ff8800", {"color": "#008080", "position": 0.25}, "#008080"]}"##,
+            PaletteFormat::Json
+        ),
+        Ok(Palette {
+            colors: vec![orange, teal, teal],
+            positions: Some(vec![0.0, 0.25, 1.0]),
+            interpolation: Interpolation::Srgb
+        })
+    );
+    assert_eq!(
+        parse(
+            r##"{"colors": ["#ff8800", "#008080"], "interpolation": "oklab"}"##,
+            PaletteFormat::Json
+        ).map(|palette| palette.interpolation),
+        Ok(Interpolation::Oklab)
+    );
+    assert_eq!(
+        parse(r##"{"colors": ["#ff8800", "#008080"]}"##, PaletteFormat::Json),
+        Ok(Palette::new(vec![orange, teal]))
+    );
+
+    // Mistakes are pointed out
+    assert_eq!(
+        parse("#ff8800\norange\n", PaletteFormat::Hex),
+        Err("line 2: `orange` isn't a hex color, like #ff8800".to_string())
+    );
+    assert_eq!(
+        parse("#ff8800\n", PaletteFormat::Hex),
+        Err("expected at least 2 colors, but got 1".to_string())
+    );
+    assert_eq!(
+        parse("#ff8800 0.5\n\n#008080 0.25\n", PaletteFormat::Hex),
+        Err("line 3: stop position 0.25 comes before the one above it".to_string())
+    );
+    assert_eq!(
+        parse("255 136 0\n", PaletteFormat::Gpl),
+        Err("expected a `GIMP Palette` header line".to_string())
+    );
+    assert_eq!(
+        parse("GIMP Palette\n255 136\n", PaletteFormat::Gpl),
+        Err("line 2: `255 136` isn't a color, like `255 136 0 Orange`".to_string())
+    );
+    assert_eq!(
+        parse(r##"{"colors": ["#ff8800", "teal"]}"##, PaletteFormat::Json),
+        Err("`teal` isn't a hex color, like #ff8800".to_string())
+    );
+    assert_eq!(
+        parse(r##"{"colors": ["#ff8800", "#008080"], "interpolation": "cmyk"}"##, PaletteFormat::Json),
+        Err(format!("invalid interpolation `cmyk`: expected {}", crate::INTERPOLATION_NAMES))
+    );
+}
+
+#[test]
+fn test_palette_format_of() {
+    assert_eq!(PaletteFormat::of(Path::new("sunset.gpl")), PaletteFormat::Gpl);
+    assert_eq!(PaletteFormat::of(Path::new("sunset.JSON")), PaletteFormat::Json);
+    assert_eq!(PaletteFormat::of(Path::new("sunset.hex")), PaletteFormat::Hex);
+    assert_eq!(PaletteFormat::of(Path::new("sunset")), PaletteFormat::Hex);
+}
@@ -0,0 +1,587 @@
+use std::path::Path;
+
+use num_complex::Complex;
+use serde::{Deserialize, Serialize};
+
+use crate::colors::{Interpolation, Palette};
+use crate::viewport::Viewport;
+use crate::{require, Args};
+
+/// The formats a `SceneFile` can be written in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SceneFormat {
+    Toml,
+    Json,
+}
+
+impl SceneFormat {
+    /// Returns the format of the file at `path`, going by its extension: JSON
+    /// for `.json`, and TOML for anything else
+    pub fn of(path: &str) -> SceneFormat {
+        let extension = Path::new(path).extension().and_then(|extension| extension.to_str());
+
+        match extension {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => SceneFormat::Json,
+            _ => SceneFormat::Toml
+        }
+    }
+}
+
+/// Everything needed to render an image again, written down in a file so that
+/// renders are repeatable and can be kept in version control, like:
+///
+/// ```toml
+/// output = "frac.png"
+/// size = [2000, 2000]
+/// fractal = "mandelbrot"
+/// iterations = 350
+/// smooth = true
+/// supersampling = "2:rotated"
+/// adaptive_antialiasing = "none"
+/// strategy = "rows"
+///
+/// [palette]
+/// name = "k8_peacock"
+/// colors = ["#c0f862", "#10a690", "#1166a3", "#3d4987", "#402b6d"]
+/// interpolation = "srgb"
+///
+/// [viewport]
+/// center = [-0.244832, -0.649801]
+/// zoom = 5208
+/// rotation = 0
+/// ```
+///
+/// Fields are spelled like the flags and options they stand for, and each
+/// one left out has the same default as its flag. The palette is written down
+/// color by color, so the scene doesn't depend on any palette file, but can
+/// also be just a name, like `palette = "k8_peacock"`. How many threads to render
+/// with isn't part of the scene, since it doesn't change the image.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneFile {
+    /// The filename of the output image
+    pub output: String,
+    /// The width and height of the output image, in pixels
+    pub size: [u32; 2],
+    /// The fractal, like `mandelbrot` or `julia:-0.8,0.156`
+    pub fractal: String,
+    /// The number of iterations with which to test points
+    pub iterations: u32,
+    /// Whether to blend colors by fractional iteration counts
+    pub smooth: bool,
+    /// Whether to spread the palette over the image's points by how many
+    /// escape at each iteration count
+    pub histogram: bool,
+    /// Samples per pixel, like `3` or `2:rotated`, or `none`
+    pub supersampling: String,
+    /// Samples per pixel on the edges between colors, like `supersampling`
+    pub adaptive_antialiasing: String,
+    /// How to split up the work between threads, like `rows` or
+    /// `tiles:64x64:hilbert`
+    pub strategy: String,
+    /// The color space to blend the palette's colors in, like `oklab`, if not
+    /// the palette's own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interpolation: Option<String>,
+    /// The color of the points that didn't escape, like `#000000`
+    pub inside: String,
+    /// How to color the points that didn't escape, like `flat` or `period`
+    pub interior: String,
+    /// How often to repeat the palette, like `32` or `log:0.5:0.25`, if it's
+    /// not stretched once over the iterations up to the limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cycle: Option<String>,
+    /// The palette, which comes after the plain fields above so that, like
+    /// the viewport, it's written as its own TOML table
+    pub palette: PaletteEntry,
+    /// The region of the complex plane to render, which comes last so that
+    /// it's written as its own TOML table after the fields above
+    pub viewport: ViewportFile,
+}
+
+/// A palette, written down as either the name of a color theme (or the name or
+/// path of a palette file), or its colors themselves
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PaletteEntry {
+    Name(String),
+    Colors(PaletteColors),
+}
+
+/// A palette's colors, like `#ff8800`, and where they sit along its gradient,
+/// if they're not spread out evenly
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaletteColors {
+    /// The color theme or palette file the colors came from, which is only a
+    /// label, and isn't looked up
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub colors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub positions: Option<Vec<f64>>,
+    /// The color space the colors are blended in, like `oklab`, which defaults
+    /// to `srgb`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interpolation: Option<String>,
+}
+
+impl PaletteEntry {
+    /// Returns `palette`, called `name`, written down color by color
+    pub fn from_palette(name: &str, palette: &Palette) -> PaletteEntry {
+        return PaletteEntry::Colors(PaletteColors {
+            name: name.to_string(),
+            colors: palette.colors.iter().map(crate::palette_file::format_hex_color).collect(),
+            positions: palette.positions.clone(),
+            interpolation: Some(palette.interpolation.to_string()),
+        });
+    }
+
+    /// Returns the palette's name, or `custom` for colors without one
+    pub fn name(&self) -> &str {
+        match self {
+            PaletteEntry::Name(name) => name,
+            PaletteEntry::Colors(colors) if colors.name.is_empty() => "custom",
+            PaletteEntry::Colors(colors) => &colors.name,
+        }
+    }
+
+    /// Validates and returns the palette written down, looking it up by name
+    /// if it's only a name
+    pub fn to_palette(&self) -> Result<Palette, String> {
+        let colors = match self {
+            PaletteEntry::Name(name) => return crate::parse_palette(name),
+            PaletteEntry::Colors(colors) => colors,
+        };
+        let interpolation = match &colors.interpolation {
+            Some(interpolation) => require(
+                crate::parse_interpolation(&format!("interpolation:{}", interpolation)),
+                "interpolation", interpolation, crate::INTERPOLATION_NAMES
+            )?,
+            None => Interpolation::Srgb
+        };
+
+        return crate::palette_file::from_hex_colors(
+            &colors.colors,
+            colors.positions.as_deref(),
+            interpolation
+        ).map_err(|error| format!("invalid palette: {}", error));
+    }
+}
+
+/// A `Viewport`, written down as either a center and a radius or zoom, or as
+/// the upper left and lower right corners, plus a rotation in degrees
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewportFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center: Option<[f64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radius: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upper_left: Option<[f64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lower_right: Option<[f64; 2]>,
+    /// How far to turn the view counterclockwise around its center, in degrees
+    pub rotation: f64,
+}
+
+impl Default for SceneFile {
+    fn default() -> SceneFile {
+        return SceneFile::from_args(&Args::default());
+    }
+}
+
+impl SceneFile {
+    /// Returns the scene that `args` render
+    pub fn from_args(args: &Args) -> SceneFile {
+        return SceneFile {
+            output: args.output_filename.clone(),
+            size: [args.image_width as u32, args.image_height as u32],
+            fractal: args.fractal.to_string(),
+            iterations: args.limit,
+            smooth: args.coloring.smooth,
+            histogram: args.coloring.equalize,
+            supersampling: args.supersampling.to_string(),
+            adaptive_antialiasing: args.adaptive_antialiasing
+                .map_or("none".to_string(), |supersampling| supersampling.to_string()),
+            strategy: args.strategy.to_string(),
+            interpolation: args.interpolation.map(|interpolation| interpolation.to_string()),
+            inside: crate::palette_file::format_hex_color(&args.coloring.inside),
+            interior: args.coloring.interior.to_string(),
+            cycle: args.coloring.cycle.map(|cycle| cycle.to_string()),
+            palette: PaletteEntry::from_palette(&args.palette_name, &args.coloring.palette),
+            viewport: ViewportFile::from_viewport(&args.viewport),
+        };
+    }
+
+    /// Validates the scene and sets everything it describes in `args`
+    ///
+    /// The viewport isn't fit to the image size yet, so that a later `--size`
+    /// can still change it.
+    pub fn apply_to(&self, args: &mut Args) -> Result<(), String> {
+        let [width, height] = self.size;
+        let size = format!("[{}, {}]", width, height);
+        let sampling_pattern = |name, value: &str| -> Result<Option<_>, String> {
+            if value == "none" {
+                return Ok(None);
+            }
+
+            return require(
+                crate::parse_sampling_pattern(value).map(Some),
+                name, value, &format!("`none` or {}", crate::SAMPLING_FORMAT)
+            );
+        };
+
+        if width == 0 || height == 0 {
+            return Err(format!("invalid size `{}`: expected [<width>, <height>], like [800, 600]", size));
+        }
+
+        if self.iterations == 0 {
+            return Err("invalid iteration limit `0`: expected a positive whole number, like 250".to_string());
+        }
+
+        args.output_filename = self.output.clone();
+        (args.image_width, args.image_height) = (width as usize, height as usize);
+        args.fractal = require(
+            crate::parse_fractal(&self.fractal),
+            "fractal", &self.fractal, crate::FRACTAL_FORMAT
+        )?;
+        args.limit = self.iterations;
+        args.coloring.palette = self.palette.to_palette()?;
+        args.palette_name = self.palette.name().to_string();
+        args.coloring.smooth = self.smooth;
+        args.coloring.equalize = self.histogram;
+        args.supersampling = sampling_pattern("supersampling", &self.supersampling)?
+            .unwrap_or(crate::antialiasing::Supersampling::None);
+        args.adaptive_antialiasing = sampling_pattern(
+            "adaptive anti-aliasing", &self.adaptive_antialiasing
+        )?;
+        args.strategy = require(
+            crate::parse_strategy(&format!("strategy:{}", self.strategy)),
+            "strategy", &self.strategy, crate::STRATEGY_NAMES
+        )?;
+        args.interpolation = match &self.interpolation {
+            Some(interpolation) => Some(require(
+                crate::parse_interpolation(&format!("interpolation:{}", interpolation)),
+                "interpolation", interpolation, crate::INTERPOLATION_NAMES
+            )?),
+            None => None
+        };
+        args.coloring.inside = require(
+            crate::parse_inside(&format!("inside:{}", self.inside)),
+            "inside color", &self.inside, crate::INSIDE_FORMAT
+        )?;
+        args.coloring.interior = require(
+            crate::parse_interior(&format!("interior:{}", self.interior)),
+            "interior", &self.interior, crate::INTERIOR_NAMES
+        )?;
+        args.coloring.cycle = match &self.cycle {
+            Some(cycle) => Some(require(
+                crate::parse_cycle(&format!("cycle:{}", cycle)),
+                "cycle", cycle, crate::CYCLE_FORMAT
+            )?),
+            None => None
+        };
+        args.viewport = self.viewport.to_viewport()?;
+
+        return Ok(());
+    }
+
+    /// Reads the scene in the file at `path`, in the format its extension says
+    pub fn load(path: &str) -> Result<SceneFile, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| format!("couldn't read scene file `{}`: {}", path, error))?;
+
+        return SceneFile::parse(&contents, SceneFormat::of(path))
+            .map_err(|error| format!("invalid scene file `{}`: {}", path, error));
+    }
+
+    /// Writes the scene to the file at `path`, in the format its extension says
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        return std::fs::write(path, self.format(SceneFormat::of(path)))
+            .map_err(|error| format!("couldn't write scene file `{}`: {}", path, error));
+    }
+
+    /// Parses a scene written in `format`
+    pub fn parse(contents: &str, format: SceneFormat) -> Result<SceneFile, String> {
+        match format {
+            SceneFormat::Toml => toml::from_str(contents).map_err(|error| error.to_string()),
+            SceneFormat::Json => serde_json::from_str(contents).map_err(|error| error.to_string()),
+        }
+    }
+
+    /// Returns the scene written in `format`
+    pub fn format(&self, format: SceneFormat) -> String {
+        // Every field is a plain string, number or array, which can always be
+        // written, so neither of these can fail
+        match format {
+            SceneFormat::Toml => toml::to_string(self).unwrap(),
+            SceneFormat::Json => serde_json::to_string_pretty(self).unwrap() + "\n",
+        }
+    }
+}
+
+impl ViewportFile {
+    /// Returns `viewport` written down as its center and radius
+    pub fn from_viewport(viewport: &Viewport) -> ViewportFile {
+        // Degrees turned into radians and back pick up rounding errors, like
+        // 30 coming back as 29.999999999999996, which a billionth of a degree
+        // is plenty to round away
+        let rotation = (viewport.rotation.to_degrees() * 1e9).round() / 1e9;
+
+        return ViewportFile {
+            center: Some([viewport.center.re, viewport.center.im]),
+            radius: Some(viewport.radius()),
+            rotation,
+            ..ViewportFile::default()
+        };
+    }
+
+    /// Validates and returns the viewport written down
+    ///
+    /// A center defaults to the default of `--center`, and a center without a
+    /// radius or zoom defaults to the default of `--zoom`.
+    pub fn to_viewport(&self) -> Result<Viewport, String> {
+        let complex = |[re, im]: [f64; 2]| Complex { re, im };
+        let positive = |value: f64| value > 0.0 && value.is_finite();
+        let mut viewport = match (self.upper_left, self.lower_right) {
+            (Some(upper_left), Some(lower_right)) => {
+                if self.center.is_some() || self.radius.is_some() || self.zoom.is_some() {
+                    return Err(
+                        "a viewport is either two corners or a center, not both".to_string()
+                    );
+                }
+
+                Viewport::try_from_corners(complex(upper_left), complex(lower_right))?
+            }
+            (None, None) => {
+                let center = self.center.map_or(crate::DEFAULT_CENTER, complex);
+                let radius = match (self.radius, self.zoom) {
+                    (Some(_), Some(_)) => {
+                        return Err("a viewport has either a radius or a zoom, not both".to_string());
+                    }
+                    (Some(radius), None) => radius,
+                    (None, Some(zoom)) => Viewport::UNZOOMED_RADIUS / zoom,
+                    (None, None) => Viewport::UNZOOMED_RADIUS / crate::DEFAULT_ZOOM,
+                };
+
+                if !positive(radius) {
+                    return Err("a viewport's radius and zoom must be positive".to_string());
+                }
+
+                Viewport::from_center_radius(center, radius)
+            }
+            _ => return Err("a viewport needs both an `upper_left` and a `lower_right`".to_string())
+        };
+
+        if !self.rotation.is_finite() {
+            return Err(format!("invalid rotation `{}`: expected a number of degrees", self.rotation));
+        }
+
+        viewport.rotation = self.rotation.to_radians();
+
+        return Ok(viewport);
+    }
+}
+
+#[test]
+fn test_scene_file_round_trip() {
+    let mut args = Args {
+        output_filename: "julia.png".to_string(),
+        image_width: 400,
+        image_height: 200,
+        fractal: crate::mandelbrot::FractalKind::Julia(Complex { re: -0.8, im: 0.156 }),
+        limit: 350,
+        coloring: crate::colors::Coloring {
+            smooth: true,
+            cycle: crate::parse_cycle("cycle:log:0.5:0.25"),
+            equalize: true,
+            inside: image::Rgb([16, 32, 48]),
+            interior: crate::colors::Interior::Period,
+            ..crate::colors::Coloring::new(crate::colors::fire())
+        },
+        palette_name: "fire".to_string(),
+        supersampling: crate::antialiasing::Supersampling::RotatedGrid(2),
+        adaptive_antialiasing: Some(crate::antialiasing::Supersampling::Grid(3)),
+        strategy: crate::parse_strategy("strategy:tiles:16x8:hilbert").unwrap(),
+        viewport: Viewport::from_center_radius(Complex { re: 0.25, im: -0.5 }, 0.125),
+        ..Args::default()
+    };
+
+    args.viewport.rotation = std::f64::consts::FRAC_PI_2;
+
+    let scene = SceneFile::from_args(&args);
+
+    assert_eq!(scene.fractal, "julia:-0.8,0.156");
+    assert_eq!(scene.supersampling, "2:rotated");
+    assert_eq!(scene.adaptive_antialiasing, "3");
+    assert_eq!(scene.strategy, "tiles:16x8:hilbert");
+    assert_eq!(scene.cycle.as_deref(), Some("log:0.5:0.25"));
+    assert!(scene.histogram);
+    assert_eq!(scene.inside, "#102030");
+    assert_eq!(scene.interior, "period");
+    assert_eq!(scene.viewport.rotation, 90.0);
+
+    // Rotations given in whole degrees are written in whole degrees
+    let mut turned = args.viewport;
+
+    turned.rotation = 30.0_f64.to_radians();
+
+    assert_eq!(ViewportFile::from_viewport(&turned).rotation, 30.0);
+
+    // Both formats read back what they wrote...
+    for &format in &[SceneFormat::Toml, SceneFormat::Json] {
+        assert_eq!(SceneFile::parse(&scene.format(format), format), Ok(scene.clone()));
+    }
+
+    // ...which sets the same arguments again
+    let mut applied = Args::default();
+
+    scene.apply_to(&mut applied).unwrap();
+
+    assert_eq!(applied.output_filename, args.output_filename);
+    assert_eq!((applied.image_width, applied.image_height), (400, 200));
+    assert_eq!(applied.fractal, args.fractal);
+    assert_eq!(applied.limit, args.limit);
+    assert_eq!(applied.coloring, args.coloring);
+    assert_eq!(applied.palette_name, args.palette_name);
+    assert_eq!(applied.supersampling, args.supersampling);
+    assert_eq!(applied.adaptive_antialiasing, args.adaptive_antialiasing);
+    assert_eq!(applied.strategy, args.strategy);
+    assert_eq!(applied.viewport, args.viewport);
+}
+
+#[test]
+fn test_scene_file_defaults() {
+    // Everything left out has the same default as its flag
+    let scene = SceneFile::parse("iterations = 99\n[viewport]\nzoom = 4\n", SceneFormat::Toml).unwrap();
+    let mut args = Args::default();
+
+    scene.apply_to(&mut args).unwrap();
+
+    assert_eq!(args.limit, 99);
+    assert_eq!(args.output_filename, crate::DEFAULT_OUTPUT);
+    assert_eq!(args.palette_name, crate::DEFAULT_PALETTE);
+    assert_eq!(args.viewport, Viewport::from_center_zoom(crate::DEFAULT_CENTER, 4.0));
+    assert_eq!(args.adaptive_antialiasing, None);
+
+    // Corners work too
+    let scene = SceneFile::parse(
+        r#"{"viewport": {"upper_left": [-2.0, 1.0], "lower_right": [1.0, -1.0]}}"#,
+        SceneFormat::Json
+    ).unwrap();
+
+    assert_eq!(
+        scene.viewport.to_viewport().unwrap().corners(),
+        (Complex { re: -2.0, im: 1.0 }, Complex { re: 1.0, im: -1.0 })
+    );
+}
+
+#[test]
+fn test_invalid_scene_files() {
+    let error = |contents: &str| {
+        let mut args = Args::default();
+
+        return SceneFile::parse(contents, SceneFormat::Toml)
+            .and_then(|scene| scene.apply_to(&mut args))
+            .err()
+            .unwrap();
+    };
+
+    assert!(error("colour = \"red\"").contains("unknown field `colour`"));
+    assert!(error("iterations = \"lots\"").contains("invalid type"));
+    assert_eq!(
+        error("iterations = 0"),
+        "invalid iteration limit `0`: expected a positive whole number, like 250"
+    );
+    assert!(error("palette = \"plaid\"").starts_with("unknown color theme `plaid`"));
+    assert_eq!(
+        error("palette = { colors = [\"#ff8800\"] }"),
+        "invalid palette: expected at least 2 colors, but got 1"
+    );
+    assert_eq!(
+        error("size = [0, 10]"),
+        "invalid size `[0, 10]`: expected [<width>, <height>], like [800, 600]"
+    );
+    assert!(error("size = [5000000000, 1]").contains("invalid value"));
+    assert_eq!(
+        error("strategy = \"columns\""),
+        "invalid strategy `columns`: expected `singlethreaded`, `segments`, `rows`, `pixels` or `tiles`"
+    );
+    assert_eq!(
+        error("supersampling = \"lots\""),
+        "invalid supersampling `lots`: expected `none` or <n>[:<pattern>], where n is from 1 to 16, like 3 or 2:rotated"
+    );
+    assert_eq!(
+        error("interior = \"plaid\""),
+        format!("invalid interior `plaid`: expected {}", crate::INTERIOR_NAMES)
+    );
+    assert_eq!(
+        error("inside = \"black\""),
+        "invalid inside color `black`: expected a hex color, like #000000"
+    );
+    assert_eq!(
+        error("cycle = \"-4\""),
+        format!("invalid cycle `-4`: expected {}", crate::CYCLE_FORMAT)
+    );
+    assert_eq!(
+        error("[viewport]\nzoom = 2\nradius = 1"),
+        "a viewport has either a radius or a zoom, not both"
+    );
+    assert_eq!(
+        error("[viewport]\nupper_left = [0, 0]"),
+        "a viewport needs both an `upper_left` and a `lower_right`"
+    );
+    assert_eq!(
+        error("[viewport]\ncenter = [0, 0]\nupper_left = [-1, 1]\nlower_right = [1, -1]"),
+        "a viewport is either two corners or a center, not both"
+    );
+}
+
+#[test]
+fn test_scene_format_of() {
+    assert_eq!(SceneFormat::of("scene.json"), SceneFormat::Json);
+    assert_eq!(SceneFormat::of("scene.JSON"), SceneFormat::Json);
+    assert_eq!(SceneFormat::of("scene.toml"), SceneFormat::Toml);
+    assert_eq!(SceneFormat::of("scene"), SceneFormat::Toml);
+}
+
+#[test]
+fn test_scene_file_palette() {
+    // A palette file's colors are written into the scene, so the scene renders
+    // the same without the file
+    let path = std::env::temp_dir().join("threadpool_fractal_test_scene_palette.json");
+    let path = path.to_str().unwrap();
+
+    std::fs::write(
+        path,
+        r##"{"colors": ["#ff8800", {"color": "#008080", "position": 0.75}, "#000000"], "interpolation": "oklab"}"##
+    ).unwrap();
+
+    let mut args = Args::default();
+
+    args.coloring.palette = crate::parse_palette(path).unwrap();
+    args.palette_name = path.to_string();
+
+    let saved = SceneFile::from_args(&args).format(SceneFormat::Toml);
+
+    std::fs::remove_file(path).unwrap();
+
+    let mut applied = Args::default();
+
+    SceneFile::parse(&saved, SceneFormat::Toml).unwrap().apply_to(&mut applied).unwrap();
+
+    assert_eq!(applied.coloring.palette, args.coloring.palette);
+    assert_eq!(applied.palette_name, path);
+
+    // Palettes can still be picked by name, which is looked up
+    let mut args = Args::default();
+
+    SceneFile::parse("palette = \"usa\"", SceneFormat::Toml).unwrap().apply_to(&mut args).unwrap();
+
+    assert_eq!(args.coloring.palette, crate::colors::usa());
+    assert_eq!(args.palette_name, "usa");
+}
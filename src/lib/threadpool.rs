@@ -1,14 +1,24 @@
-// This is the thread pool from the book:
+// This started as the thread pool from the book:
 // https://doc.rust-lang.org/stable/book/ch20-00-final-project-a-web-server.html
 //
+// but, instead of all threads taking jobs from one channel behind one lock,
+// each thread has its own queue of jobs, and a thread that runs out of jobs
+// steals them from the back of other threads' queues. this keeps threads from
+// waiting on each other for the next job, which matters when jobs are tiny
+// (like rendering a single pixel).
+//
 // # usage
 //
 //     let pool = ThreadPool::new(4); // use 4 threads
+//     // or
+//     let pool = ThreadPool::default(); // use as many threads as we have cpus
 //
 //     pool.execute(|| {
 //         foo();
 //     });
 //
+//     pool.join(); // wait for all jobs to finish
+//
 // where `foo()` is the function to parallelize
 //
 // # example
@@ -30,20 +40,206 @@
 //             foo(i_inner);
 //         });
 //     }
+//
+//     pool.join(); // now, i == N, and the pool can take more jobs
+//
+// # results
+//
+// to get a value back out of a job, use `submit` instead of `execute`:
+//
+//     let handle = pool.submit(|| 6 * 7);
+//
+//     assert_eq!(handle.wait().unwrap(), 42);
+//
+// # borrowing
+//
+// jobs given to `execute` and `submit` can't borrow anything, since they
+// could outlive it. to lend jobs something, like disjoint parts of a buffer,
+// use `scope`, which waits for its jobs before returning:
+//
+//     let mut numbers = vec![1, 2, 3, 4];
+//
+//     pool.scope(|scope| {
+//         for number in numbers.iter_mut() {
+//             scope.execute(move || *number *= 2);
+//         }
+//     });
+//
+//     assert_eq!(numbers, vec![2, 4, 6, 8]);
+//
+// # panics
+//
+// a job that panics doesn't take its thread down with it: the thread carries
+// on with the next job, `submit`'s handle returns the panic from `wait`, and
+// `panicked_jobs` counts every panic so far
 use std::thread;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
 
 /// Task to execute per thread
 type Job = Box<dyn FnOnce() + Send + 'static>;
 
-/// Wrapper for Job, indicating further work or shutdown
-enum Message {
-    NewJob(Job),
-    Terminate,
+/// The job queues of all Workers, plus what idle Workers need to sleep until
+/// there's a job for them
+struct Queues {
+    /// One queue per Worker, indexed by Worker id
+    queues: Vec<Mutex<VecDeque<Job>>>,
+    /// Number of jobs in all queues, counted up *before* a job is pushed, so
+    /// it's never less than the real number
+    queued: AtomicUsize,
+    /// Number of Workers asleep (or about to be) on `job_queued`
+    sleeping: AtomicUsize,
+    /// Set when the pool is dropped, telling Workers to quit once all queues
+    /// are empty
+    shutdown: AtomicBool,
+    /// Lock for sleeping Workers to wait on `job_queued` with
+    sleep_lock: Mutex<()>,
+    job_queued: Condvar,
+    /// Queue that the next job from outside the pool goes to
+    next_queue: AtomicUsize,
+}
+
+impl Queues {
+    fn new(size: usize) -> Queues {
+        Queues {
+            queues: (0..size).map(|_| Mutex::new(VecDeque::new())).collect(),
+            queued: AtomicUsize::new(0),
+            sleeping: AtomicUsize::new(0),
+            shutdown: AtomicBool::new(false),
+            sleep_lock: Mutex::new(()),
+            job_queued: Condvar::new(),
+            next_queue: AtomicUsize::new(0),
+        }
+    }
+
+    /// Locks the queue of Worker `id`
+    ///
+    /// Jobs never run while a queue is locked, so nothing should poison one,
+    /// but if something ever does, the queue is still perfectly usable.
+    fn queue(&self, id: usize) -> MutexGuard<'_, VecDeque<Job>> {
+        self.queues[id].lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds `job` to the back of the next queue, round-robin, and wakes a
+    /// sleeping Worker to take it
+    fn push(&self, job: Job) {
+        let id = self.next_queue.fetch_add(1, Ordering::Relaxed) % self.queues.len();
+
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        self.queue(id).push_back(job);
+
+        // A Worker going to sleep counts itself as sleeping before checking
+        // `queued`, and we counted the job before checking `sleeping`, so
+        // either it sees our job or we see it sleeping
+        if self.sleeping.load(Ordering::SeqCst) > 0 {
+            let _guard = self.sleep_lock.lock().unwrap();
+
+            self.job_queued.notify_one();
+        }
+    }
+
+    /// Takes the job at the front of Worker `id`'s queue or, if that's empty,
+    /// steals the job at the back of another Worker's queue
+    fn pop(&self, id: usize) -> Option<Job> {
+        let count = self.queues.len();
+        // Let go of our own queue before locking anyone else's, or two Workers
+        // robbing each other would each wait on the other's lock forever
+        let own_job = self.queue(id).pop_front();
+        let job = own_job.or_else(|| {
+            // Start with the next Worker over, so thieves spread out instead
+            // of all robbing Worker 0
+            (1..count)
+                .map(|offset| (id + offset) % count)
+                .find_map(|victim| self.queue(victim).pop_back())
+        });
+
+        if job.is_some() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        job
+    }
+
+    /// Blocks until there might be a job to take, returning `false` if there
+    /// never will be because the pool is shutting down
+    fn wait_for_job(&self) -> bool {
+        let mut guard = self.sleep_lock.lock().unwrap();
+
+        self.sleeping.fetch_add(1, Ordering::SeqCst);
+
+        while self.queued.load(Ordering::SeqCst) == 0 && !self.shutdown.load(Ordering::SeqCst) {
+            guard = self.job_queued.wait(guard).unwrap();
+        }
+
+        self.sleeping.fetch_sub(1, Ordering::SeqCst);
+
+        // Finish every queued job before shutting down
+        self.queued.load(Ordering::SeqCst) > 0
+    }
+
+    /// Tells all Workers to quit once all queues are empty
+    fn shut_down(&self) {
+        let _guard = self.sleep_lock.lock().unwrap();
+
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.job_queued.notify_all();
+    }
+}
+
+/// Count of jobs that were sent but haven't finished yet, which is signaled
+/// whenever it drops to zero, plus a count of finished jobs that panicked
+struct PendingJobs {
+    count: Mutex<usize>,
+    all_done: Condvar,
+    panicked: Mutex<usize>,
+}
+
+impl PendingJobs {
+    fn new() -> PendingJobs {
+        PendingJobs {
+            count: Mutex::new(0),
+            all_done: Condvar::new(),
+            panicked: Mutex::new(0),
+        }
+    }
+
+    fn add(&self) {
+        *self.count.lock().unwrap() += 1;
+    }
+
+    fn record_panic(&self) {
+        *self.panicked.lock().unwrap() += 1;
+    }
+
+    fn finish(&self, panicked: bool) {
+        if panicked {
+            self.record_panic();
+        }
+
+        let mut count = self.count.lock().unwrap();
+
+        *count -= 1;
+
+        if *count == 0 {
+            self.all_done.notify_all();
+        }
+    }
+
+    /// Blocks until the count is zero
+    fn wait(&self) {
+        let mut count = self.count.lock().unwrap();
+
+        while *count > 0 {
+            count = self.all_done.wait(count).unwrap();
+        }
+    }
 }
 
-/// Creates a thread and waits for Messages
+/// Creates a thread and runs jobs from its queue, or other Workers' queues
 #[allow(dead_code)]
 struct Worker {
     id: usize,
@@ -51,16 +247,19 @@ struct Worker {
 }
 
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
+    fn new(id: usize, queues: Arc<Queues>, pending: Arc<PendingJobs>) -> Worker {
         let thread = thread::spawn(move || loop {
-            let message = receiver.lock().unwrap().recv().unwrap();
+            match queues.pop(id) {
+                Some(job) => {
+                    // Catch a panicking job here, so it doesn't kill the thread
+                    let result = panic::catch_unwind(AssertUnwindSafe(job));
 
-            match message {
-                Message::NewJob(job) => {
-                    job();
+                    pending.finish(result.is_err());
                 }
-                Message::Terminate => {
-                    break;
+                None => {
+                    if !queues.wait_for_job() {
+                        break;
+                    }
                 }
             }
         });
@@ -75,7 +274,8 @@ impl Worker {
 /// Takes jobs and gives them to a pool of threads
 pub struct ThreadPool {
     workers: Vec<Worker>,
-    sender: mpsc::Sender<Message>,
+    queues: Arc<Queues>,
+    pending: Arc<PendingJobs>,
 }
 
 impl ThreadPool {
@@ -86,47 +286,406 @@ impl ThreadPool {
     /// # Panics
     ///
     /// The `new` function will panic if the size is zero.
-    // TODO: make argumentless ctor default to an appropriate number of threads
-    // depending on the system's cpu
     pub fn new(size: usize) -> ThreadPool {
         assert!(size > 0);
 
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let queues = Arc::new(Queues::new(size));
+        let pending = Arc::new(PendingJobs::new());
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+            workers.push(Worker::new(id, Arc::clone(&queues), Arc::clone(&pending)));
         }
 
-        ThreadPool { workers, sender }
+        ThreadPool { workers, queues, pending }
+    }
+
+    /// Create a new ThreadPool with one thread per cpu this process can use
+    ///
+    /// See `available_threads`.
+    pub fn with_available_parallelism() -> ThreadPool {
+        ThreadPool::new(available_threads())
+    }
+
+    /// Returns the number of threads in the pool
+    pub fn size(&self) -> usize {
+        self.workers.len()
     }
 
-    /// Take a task as a closure and send it in a Message to the Workers
+    /// Take a task as a closure and queue it up for the Workers
     pub fn execute<F>(&self, f: F)
     where
         F: FnOnce() + Send + 'static,
         {
-            let job = Box::new(f);
+            self.execute_boxed(Box::new(f));
+        }
+
+    fn execute_boxed(&self, job: Job) {
+        self.pending.add();
+        self.queues.push(job);
+    }
+
+    /// Take a task as a closure that returns a value, send it to the Workers,
+    /// and return a handle for getting that value once the task is done
+    pub fn submit<F, T>(&self, f: F) -> JobHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+        {
+            let (sender, receiver) = mpsc::channel();
+
+            self.execute(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                let panicked = result.is_err();
+
+                // The handle may have been dropped, in which case nobody
+                // wants the result
+                let _ = sender.send(result);
+
+                // Re-panic so the pool counts this job as panicked, too
+                if panicked {
+                    panic::resume_unwind(Box::new("job panicked"));
+                }
+            });
+
+            JobHandle { receiver }
+        }
+
+    /// Run `f` with a `Scope` for giving the Workers jobs that borrow from
+    /// outside of it, and return what `f` returns once all those jobs have
+    /// finished
+    ///
+    /// Panicking jobs are counted by `panicked_jobs`, as usual, and don't
+    /// panic the caller.
+    ///
+    /// Don't call this from one of this pool's jobs, since the job's thread
+    /// would wait on jobs that might need that very thread to run.
+    pub fn scope<'env, F, T>(&self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+        {
+            let scope = Scope {
+                pool: self,
+                pending: Arc::new(PendingJobs::new()),
+                scope: PhantomData,
+                env: PhantomData,
+            };
 
-            self.sender.send(Message::NewJob(job)).unwrap();
+            // Even if `f` panics, its jobs may still be borrowing from it, so
+            // they must finish before we go anywhere
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+
+            scope.pending.wait();
+
+            match result {
+                Ok(value) => value,
+                Err(panic) => panic::resume_unwind(panic),
+            }
         }
+
+    /// Block until every job given to `execute` or `submit` so far has finished
+    ///
+    /// Unlike dropping the pool, this keeps the threads alive, so the pool can
+    /// take more jobs afterward (like the next frame of an animation).
+    pub fn join(&self) {
+        self.pending.wait();
+    }
+
+    /// Returns how many jobs have panicked since the pool was created
+    ///
+    /// A job counts once its thread is done with it, which can be a moment
+    /// after its handle's `wait` returns, so `join` first for an exact count.
+    pub fn panicked_jobs(&self) -> usize {
+        *self.pending.panicked.lock().unwrap()
+    }
+}
+
+impl Default for ThreadPool {
+    fn default() -> ThreadPool {
+        ThreadPool::with_available_parallelism()
+    }
+}
+
+/// Returns the number of threads this process can run in parallel, or 1 if
+/// that can't be found out
+///
+/// This is usually the number of cpus, but less if the process is limited to
+/// fewer of them, like by cpu affinity or a container's cgroup cpu quota.
+pub fn available_threads() -> usize {
+    thread::available_parallelism()
+        .map(|threads| threads.get())
+        .unwrap_or(1)
+}
+
+/// Lets jobs given to its `execute` borrow anything that outlives the call to
+/// `ThreadPool::scope` that created it
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    /// The scope's own jobs, which `ThreadPool::scope` waits for
+    pending: Arc<PendingJobs>,
+    // Keep both lifetimes exact (invariant), like `std::thread::Scope` does
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Take a task as a closure, which may borrow from outside the scope, and
+    /// queue it up for the Workers
+    pub fn execute<F>(&'scope self, f: F)
+    where
+        F: FnOnce() + Send + 'scope,
+        {
+            let scope_pending = Arc::clone(&self.pending);
+            let pool_pending = Arc::clone(&self.pool.pending);
+
+            scope_pending.add();
+
+            let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+
+                if result.is_err() {
+                    pool_pending.record_panic();
+                }
+
+                scope_pending.finish(result.is_err());
+            });
+
+            // SAFETY: the job only lives as long as 'scope, but Workers want
+            // 'static jobs, since they could run them any time. That's fine
+            // because `ThreadPool::scope` doesn't return (and so, 'scope
+            // doesn't end) until `scope_pending` says this job has finished.
+            let job: Job = unsafe {
+                std::mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Job>(job)
+            };
+
+            self.pool.execute_boxed(job);
+        }
+}
+
+/// The result of a job given to `ThreadPool::submit`, which will be ready once
+/// the job has finished
+pub struct JobHandle<T> {
+    receiver: mpsc::Receiver<thread::Result<T>>,
+}
+
+impl<T> JobHandle<T> {
+    /// Block until the job has finished and return its result
+    ///
+    /// Like `std::thread::JoinHandle::join`, this returns an `Err` with the
+    /// job's panic if the job panicked instead of returning.
+    pub fn wait(self) -> thread::Result<T> {
+        match self.receiver.recv() {
+            Ok(result) => result,
+            // The job was dropped without running, which only happens if its
+            // thread died
+            Err(_) => Err(Box::new("job was lost")),
+        }
+    }
 }
 
 // when the pool is dropped, our threads should all join to make sure they finish their work.
 impl Drop for ThreadPool {
     fn drop(&mut self) {
-        for _ in &self.workers {
-            self.sender.send(Message::Terminate).unwrap();
-        }
+        self.queues.shut_down();
 
         for worker in &mut self.workers {
             if let Some(thread) = worker.thread.take() {
-                // take ownership of the Worker's thread and have it finish
-                thread.join().unwrap();
+                // take ownership of the Worker's thread and have it finish;
+                // jobs can't panic it, and if something else did, there's
+                // nothing left to clean up, so don't panic the dropper, too
+                let _ = thread.join();
             }
             // if it's None, this Worker has already had its thread cleaned up
         }
     }
 }
  
+
+#[test]
+fn test_join() {
+    let pool = ThreadPool::new(4);
+    let count = Arc::new(Mutex::new(0));
+
+    // Join as many times as we like, waiting on each batch of jobs in turn
+    for batch in 1..=3 {
+        for _ in 0..100 {
+            let job_count = Arc::clone(&count);
+
+            pool.execute(move || {
+                thread::sleep(std::time::Duration::from_micros(10));
+                *job_count.lock().unwrap() += 1;
+            });
+        }
+
+        pool.join();
+
+        assert_eq!(*count.lock().unwrap(), batch * 100);
+    }
+
+    // Joining with no jobs returns right away
+    pool.join();
+}
+
+#[test]
+fn test_submit() {
+    let pool = ThreadPool::new(4);
+
+    let handles: Vec<JobHandle<u64>> = (0..100u64)
+        .map(|i| pool.submit(move || i * i))
+        .collect();
+
+    // Results come back to the right handles, no matter the order jobs finish
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.wait().unwrap(), (i * i) as u64);
+    }
+
+    // Dropping a handle doesn't bother the job
+    drop(pool.submit(|| "nobody's waiting"));
+    pool.join();
+}
+
+#[test]
+fn test_panicking_jobs() {
+    let pool = ThreadPool::new(2);
+
+    // Panic more times than there are threads...
+    for _ in 0..10 {
+        pool.execute(|| panic!("bad tile"));
+    }
+
+    let handle = pool.submit(|| -> u32 { panic!("worse tile") });
+
+    match handle.wait() {
+        Ok(_) => panic!("job should have panicked"),
+        Err(panic) => assert_eq!(*panic.downcast_ref::<&str>().unwrap(), "worse tile"),
+    }
+
+    pool.join();
+
+    assert_eq!(pool.panicked_jobs(), 11);
+
+    // ...and the pool still works
+    let handles: Vec<JobHandle<u32>> = (0..10).map(|i| pool.submit(move || i + 1)).collect();
+    let sum: u32 = handles.into_iter().map(|handle| handle.wait().unwrap()).sum();
+
+    assert_eq!(sum, 55);
+    assert_eq!(pool.panicked_jobs(), 11);
+}
+
+#[test]
+fn test_default_size() {
+    let pool = ThreadPool::default();
+
+    assert!(pool.size() >= 1);
+    assert_eq!(pool.size(), available_threads());
+}
+
+#[test]
+fn test_uneven_jobs() {
+    let pool = ThreadPool::new(4);
+    let (release, blocked) = mpsc::channel::<()>();
+    let (done_sender, done) = mpsc::channel();
+
+    // Round-robin queues every 4th job behind the first one, which stays
+    // blocked until the rest are done, so they only finish if other Workers
+    // steal them
+    pool.execute(move || {
+        blocked.recv().unwrap();
+    });
+
+    for _ in 1..400 {
+        let job_done = done_sender.clone();
+
+        pool.execute(move || {
+            job_done.send(()).unwrap();
+        });
+    }
+
+    for _ in 1..400 {
+        // The timeout only keeps a broken pool from hanging the test
+        done.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
+    }
+
+    release.send(()).unwrap();
+    pool.join();
+}
+
+#[test]
+fn test_workers_robbing_each_other() {
+    let pool = ThreadPool::new(4);
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    // Jobs this tiny keep queues running dry, so Workers are constantly
+    // stealing from each other at the same time
+    for _ in 0..100_000 {
+        let job_finished = Arc::clone(&finished);
+
+        pool.execute(move || {
+            job_finished.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+
+    pool.join();
+
+    assert_eq!(finished.load(Ordering::SeqCst), 100_000);
+}
+
+#[test]
+fn test_drop_finishes_queued_jobs() {
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    {
+        let pool = ThreadPool::new(2);
+
+        for _ in 0..100 {
+            let job_finished = Arc::clone(&finished);
+
+            pool.execute(move || {
+                job_finished.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+    }
+
+    assert_eq!(finished.load(Ordering::SeqCst), 100);
+}
+
+#[test]
+fn test_scope() {
+    let pool = ThreadPool::new(4);
+    let mut numbers: Vec<u32> = (0..100).collect();
+    let offset = 1000;
+
+    // Jobs mutably borrow their own numbers, and share `offset`
+    let returned = pool.scope(|scope| {
+        for chunk in numbers.chunks_mut(7) {
+            let offset = &offset;
+
+            scope.execute(move || {
+                for number in chunk {
+                    *number += offset;
+                }
+            });
+        }
+
+        "done"
+    });
+
+    assert_eq!(returned, "done");
+    assert_eq!(numbers, (1000..1100).collect::<Vec<u32>>());
+
+    // Panics are counted, not passed on
+    let mut untouched = [0, 0];
+
+    pool.scope(|scope| {
+        let (first, second) = untouched.split_at_mut(1);
+
+        scope.execute(move || {
+            first[0] = 1;
+            panic!("halfway");
+        });
+        scope.execute(move || second[0] = 2);
+    });
+
+    assert_eq!(untouched, [1, 2]);
+    assert_eq!(pool.panicked_jobs(), 1);
+}
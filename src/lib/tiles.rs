@@ -0,0 +1,239 @@
+use image::RgbImage;
+
+use crate::{Fractal, Scene};
+
+/// A reasonable tile size, in pixels, for `render_multithreaded_pooled_tiles`
+///
+/// Small enough that there are plenty of tiles to go around, and big enough
+/// that rendering one takes much longer than handing it to a thread.
+pub const DEFAULT_TILE_SIZE: (u32, u32) = (32, 32);
+
+/// The order in which tiles are handed to threads, and so, roughly, the order
+/// in which they're rendered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom, like reading
+    RowMajor,
+    /// Outward from the center of the image, ring by ring, so the middle of
+    /// the view (usually the interesting part) shows up first
+    Spiral,
+    /// Along a Hilbert curve, which keeps consecutive tiles next to each other,
+    /// so threads working at the same time work on nearby parts of the image
+    Hilbert,
+}
+
+/// Returns the (column, row) of every tile in a grid `tiles_across` tiles wide
+/// and `tiles_down` tiles tall, in `order`
+pub fn order_tiles(order: TileOrder, tiles_across: u32, tiles_down: u32) -> Vec<(u32, u32)> {
+    let mut tiles: Vec<(u32, u32)> = (0..tiles_down)
+        .flat_map(|row| (0..tiles_across).map(move |column| (column, row)))
+        .collect();
+
+    match order {
+        TileOrder::RowMajor => {}
+        TileOrder::Spiral => {
+            // Sort by which square ring around the center a tile is on, then
+            // clockwise from straight up. Coordinates are doubled so the
+            // center is a whole number even between two tiles.
+            let spiral_key = |&(column, row): &(u32, u32)| {
+                let dx = (2 * column + 1) as i64 - tiles_across as i64;
+                let dy = (2 * row + 1) as i64 - tiles_down as i64;
+                let ring = dx.abs().max(dy.abs());
+                let mut angle = (dx as f64).atan2(-dy as f64);
+
+                if angle < 0.0 {
+                    angle += 2.0 * std::f64::consts::PI;
+                }
+
+                return (ring, angle);
+            };
+
+            tiles.sort_by(|a, b| spiral_key(a).partial_cmp(&spiral_key(b)).unwrap());
+        }
+        TileOrder::Hilbert => {
+            let side = tiles_across.max(tiles_down).next_power_of_two();
+
+            tiles.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+
+    return tiles;
+}
+
+#[test]
+fn test_order_tiles() {
+    assert_eq!(
+        order_tiles(TileOrder::RowMajor, 3, 2),
+        vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+    );
+
+    // The center first, then clockwise around it from straight up
+    assert_eq!(
+        order_tiles(TileOrder::Spiral, 3, 3),
+        vec![
+            (1, 1),
+            (1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (0, 2), (0, 1), (0, 0)
+        ]
+    );
+
+    assert_eq!(
+        order_tiles(TileOrder::Hilbert, 2, 2),
+        vec![(0, 0), (0, 1), (1, 1), (1, 0)]
+    );
+
+    // Every step of a Hilbert curve goes to a neighboring tile
+    let hilbert = order_tiles(TileOrder::Hilbert, 8, 8);
+
+    assert_eq!(hilbert.len(), 64);
+
+    for step in hilbert.windows(2) {
+        let (a, b) = (step[0], step[1]);
+
+        assert_eq!(a.0.max(b.0) - a.0.min(b.0) + a.1.max(b.1) - a.1.min(b.1), 1);
+    }
+}
+
+/// Returns how far along a Hilbert curve filling a `side`×`side` grid (where
+/// `side` is a power of two) the cell at (`x`, `y`) is
+fn hilbert_index(side: u32, x: u32, y: u32) -> u64 {
+    let (mut x, mut y) = (x, y);
+    let mut index: u64 = 0;
+    let mut s = side / 2;
+
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        // Rotate the quadrant so the curve within it lines up with the curve
+        // at the next level down
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        s /= 2;
+    }
+
+    return index;
+}
+
+/// A rectangle of the output image, borrowed row by row so that a thread can
+/// render it without locking the whole image
+pub(crate) struct Tile<'a> {
+    /// Coordinates of the tile's upper left pixel in the whole image
+    x: u32,
+    y: u32,
+    /// The tile's rows' pixel channels, three (RGB) per pixel
+    rows: Vec<&'a mut [u8]>,
+}
+
+impl<'a> Tile<'a> {
+    /// Returns the coordinates of the tile's upper left pixel in the whole image
+    #[cfg(test)]
+    pub(crate) fn position(&self) -> (u32, u32) {
+        return (self.x, self.y);
+    }
+
+    /// Returns the tile's width and height, in pixels
+    #[cfg(test)]
+    pub(crate) fn size(&self) -> (u32, u32) {
+        let width = self.rows.first().map_or(0, |row| row.len() / 3);
+
+        return (width as u32, self.rows.len() as u32);
+    }
+
+    /// Renders all of the tile's pixels of `scene`, whose image is `width`×
+    /// `height` pixels
+    pub(crate) fn render<F: Fractal>(&mut self, scene: &Scene<F>, width: u32, height: u32) {
+        let (x, y) = (self.x, self.y);
+
+        for (row_y, row) in self.rows.iter_mut().enumerate() {
+            for (column_x, pixel) in row.chunks_mut(3).enumerate() {
+                let coords = (x + column_x as u32, y + row_y as u32);
+
+                pixel.copy_from_slice(&scene.pixel_color(coords, width, height).0);
+            }
+        }
+    }
+}
+
+/// Splits `pixels` into tiles of (at most) `tile_size` (width, height) pixels,
+/// in `order`
+///
+/// Tiles along the right and bottom edges are cut short if the image isn't a
+/// whole number of tiles across or down.
+pub(crate) fn divide_image_into_tiles(
+    pixels: &mut RgbImage,
+    tile_size: (u32, u32),
+    order: TileOrder
+) -> Vec<Tile<'_>> {
+    let (width, height) = pixels.dimensions();
+    let (tile_width, tile_height) = tile_size;
+
+    assert!(tile_width > 0 && tile_height > 0, "tiles must be at least 1×1 pixels");
+
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+
+    let tiles_across = width.div_ceil(tile_width);
+    let tiles_down = height.div_ceil(tile_height);
+
+    // Lay out the tiles in row-major order...
+    let mut grid: Vec<Tile> = (0..tiles_down)
+        .flat_map(|row| (0..tiles_across).map(move |column| (column, row)))
+        .map(|(column, row)| Tile {
+            x: column * tile_width,
+            y: row * tile_height,
+            rows: Vec::with_capacity(tile_height as usize),
+        })
+        .collect();
+
+    // ...give each tile its piece of every image row it covers...
+    for (y, image_row) in pixels.chunks_mut(width as usize * 3).enumerate() {
+        let first_tile = (y as u32 / tile_height * tiles_across) as usize;
+
+        for (column, piece) in image_row.chunks_mut(tile_width as usize * 3).enumerate() {
+            grid[first_tile + column].rows.push(piece);
+        }
+    }
+
+    // ...and then hand them out in the order asked for
+    let mut grid: Vec<Option<Tile>> = grid.into_iter().map(Some).collect();
+
+    return order_tiles(order, tiles_across, tiles_down)
+        .into_iter()
+        .filter_map(|(column, row)| grid[(row * tiles_across + column) as usize].take())
+        .collect();
+}
+
+#[test]
+fn test_divide_image_into_tiles() {
+    let mut img = RgbImage::new(5, 3);
+    let tiles = divide_image_into_tiles(&mut img, (2, 2), TileOrder::RowMajor);
+
+    // Correct number of tiles
+    assert_eq!(tiles.len(), 6);
+
+    // Correct positions of tiles
+    let positions: Vec<(u32, u32)> = tiles.iter().map(Tile::position).collect();
+
+    assert_eq!(positions, vec![(0, 0), (2, 0), (4, 0), (0, 2), (2, 2), (4, 2)]);
+
+    // Correct sizes of tiles, which are cut short along the edges
+    let sizes: Vec<(u32, u32)> = tiles.iter().map(Tile::size).collect();
+
+    assert_eq!(sizes, vec![(2, 2), (2, 2), (1, 2), (2, 1), (2, 1), (1, 1)]);
+
+    // Tiles come out in the order asked for
+    let tiles = divide_image_into_tiles(&mut img, (2, 2), TileOrder::Hilbert);
+    let positions: Vec<(u32, u32)> = tiles.iter().map(Tile::position).collect();
+
+    assert_eq!(positions, vec![(0, 0), (2, 0), (2, 2), (0, 2), (4, 2), (4, 0)]);
+}
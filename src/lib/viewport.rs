@@ -0,0 +1,248 @@
+use num_complex::Complex;
+
+/// A rectangle of the complex plane to render, described by its center, how
+/// far it reaches from there, and how it's turned around it
+///
+/// It can be built from the rectangle's corners, or from a center and a zoom
+/// or radius, and should be `fit` to the image it's rendered to so that
+/// pixels come out square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// The point of the complex plane in the middle of the view
+    pub center: Complex<f64>,
+    /// Half the width of the view, along the real axis
+    pub half_width: f64,
+    /// Half the height of the view, along the imaginary axis
+    pub half_height: f64,
+    /// The angle, in radians, the view is turned counterclockwise around its
+    /// center, which turns what's in the image clockwise
+    ///
+    /// `half_width` and `half_height` are measured along the view's own turned
+    /// axes.
+    pub rotation: f64,
+}
+
+impl Viewport {
+    /// How far a view with a zoom of 1 reaches from its center, which is just
+    /// enough to fit the whole Mandelbrot set around its center
+    pub const UNZOOMED_RADIUS: f64 = 2.0;
+
+    /// Returns the view between the `upper_left` and `lower_right` corners
+    pub fn from_corners(upper_left: Complex<f64>, lower_right: Complex<f64>) -> Viewport {
+        return Viewport {
+            center: (upper_left + lower_right) / 2.0,
+            half_width: (lower_right.re - upper_left.re) / 2.0,
+            half_height: (upper_left.im - lower_right.im) / 2.0,
+            rotation: 0.0,
+        };
+    }
+
+    /// Returns the view between the `upper_left` and `lower_right` corners, or
+    /// an error if they're the wrong way around, which would render the view
+    /// mirrored, or on top of each other
+    pub fn try_from_corners(
+        upper_left: Complex<f64>,
+        lower_right: Complex<f64>
+    ) -> Result<Viewport, String> {
+        let viewport = Viewport::from_corners(upper_left, lower_right);
+        let positive = |value: f64| value > 0.0 && value.is_finite();
+
+        if !positive(viewport.half_width) || !positive(viewport.half_height) {
+            return Err("a viewport's upper left corner must be above and left of its \
+                lower right corner".to_string());
+        }
+
+        return Ok(viewport);
+    }
+
+    /// Returns the square view reaching `radius` from `center` up, down, left
+    /// and right
+    pub fn from_center_radius(center: Complex<f64>, radius: f64) -> Viewport {
+        return Viewport { center, half_width: radius, half_height: radius, rotation: 0.0 };
+    }
+
+    /// Returns the square view around `center`, zoomed in `zoom` times from one
+    /// reaching `UNZOOMED_RADIUS` from it
+    pub fn from_center_zoom(center: Complex<f64>, zoom: f64) -> Viewport {
+        return Viewport::from_center_radius(center, Viewport::UNZOOMED_RADIUS / zoom);
+    }
+
+    /// Returns the upper left and lower right corners of the view, as if it
+    /// weren't turned
+    pub fn corners(&self) -> (Complex<f64>, Complex<f64>) {
+        let reach = Complex { re: self.half_width, im: -self.half_height };
+
+        return (self.center - reach, self.center + reach);
+    }
+
+    /// Returns how far the view reaches from its center along its shorter side
+    pub fn radius(&self) -> f64 {
+        return self.half_width.min(self.half_height);
+    }
+
+    /// Returns how many times the view is zoomed in from one reaching
+    /// `UNZOOMED_RADIUS` from its center
+    pub fn zoom(&self) -> f64 {
+        return Viewport::UNZOOMED_RADIUS / self.radius();
+    }
+
+    /// Returns the view, widened or heightened around the same center, with the
+    /// same aspect ratio as an image `width`×`height` pixels big
+    ///
+    /// Rendering a view with a different aspect ratio than its image stretches
+    /// the image. Fitting the view first keeps pixels square, without cropping
+    /// anything out of the view.
+    pub fn fit(&self, width: u32, height: u32) -> Viewport {
+        if width == 0 || height == 0 {
+            return *self;
+        }
+
+        let aspect_ratio = width as f64 / height as f64;
+        let mut fitted = *self;
+
+        if self.half_width / self.half_height < aspect_ratio {
+            fitted.half_width = self.half_height * aspect_ratio;
+        }
+        else {
+            fitted.half_height = self.half_width / aspect_ratio;
+        }
+
+        return fitted;
+    }
+
+    /// Returns the point of the view at the fractional pixel coordinates
+    /// `pixel_coords` of an image `width`×`height` pixels big
+    pub fn subpixel_to_complex_point(
+        &self,
+        pixel_coords: (f64, f64),
+        width: u32,
+        height: u32
+    ) -> Complex<f64> {
+        let (upper_left, lower_right) = self.corners();
+        let point = crate::mandelbrot::subpixel_to_complex_point(
+            pixel_coords,
+            width, height,
+            upper_left,
+            lower_right
+        );
+
+        if self.rotation == 0.0 {
+            return point;
+        }
+
+        // Multiplying by a unit complex number turns a point around the origin
+        // by its angle, so turn the point's offset from the center
+        return self.center + (point - self.center) * Complex::from_polar(1.0, self.rotation);
+    }
+}
+
+#[test]
+fn test_viewport_corners() {
+    let upper_left = Complex { re: -2.5, im: 1.0 };
+    let lower_right = Complex { re: 1.5, im: -1.0 };
+    let viewport = Viewport::from_corners(upper_left, lower_right);
+
+    assert_eq!(viewport.center, Complex { re: -0.5, im: 0.0 });
+    assert_eq!((viewport.half_width, viewport.half_height), (2.0, 1.0));
+    assert_eq!(viewport.corners(), (upper_left, lower_right));
+    assert_eq!(viewport.radius(), 1.0);
+    assert_eq!(viewport.zoom(), 2.0);
+}
+
+#[test]
+fn test_viewport_inverted_corners() {
+    let upper_left = Complex { re: -2.0, im: 1.0 };
+    let lower_right = Complex { re: 2.0, im: -1.0 };
+
+    assert_eq!(
+        Viewport::try_from_corners(upper_left, lower_right),
+        Ok(Viewport::from_corners(upper_left, lower_right))
+    );
+
+    // Mirrored both ways, left to right, top to bottom, or with no size at all
+    let upper_right = Complex { re: 2.0, im: 1.0 };
+    let lower_left = Complex { re: -2.0, im: -1.0 };
+
+    assert!(Viewport::try_from_corners(lower_right, upper_left).is_err());
+    assert!(Viewport::try_from_corners(upper_right, lower_left).is_err());
+    assert!(Viewport::try_from_corners(lower_left, upper_right).is_err());
+    assert!(Viewport::try_from_corners(upper_left, upper_left).is_err());
+}
+
+#[test]
+fn test_viewport_center() {
+    let center = Complex { re: -0.75, im: 0.25 };
+
+    assert_eq!(
+        Viewport::from_center_radius(center, 0.5).corners(),
+        (Complex { re: -1.25, im: 0.75 }, Complex { re: -0.25, im: -0.25 })
+    );
+    assert_eq!(Viewport::from_center_zoom(center, 8.0), Viewport::from_center_radius(center, 0.25));
+    assert_eq!(Viewport::from_center_zoom(center, 8.0).zoom(), 8.0);
+}
+
+#[test]
+fn test_viewport_fit() {
+    let square = Viewport::from_center_radius(Complex { re: 0.0, im: 0.0 }, 1.0);
+
+    // Wider images see further to the sides...
+    assert_eq!(
+        square.fit(200, 100).corners(),
+        (Complex { re: -2.0, im: 1.0 }, Complex { re: 2.0, im: -1.0 })
+    );
+
+    // ...and taller ones further up and down
+    assert_eq!(
+        square.fit(100, 300).corners(),
+        (Complex { re: -1.0, im: 3.0 }, Complex { re: 1.0, im: -3.0 })
+    );
+
+    // Views that already fit are left alone
+    assert_eq!(square.fit(100, 100), square);
+    assert_eq!(square.fit(200, 100).fit(400, 200), square.fit(200, 100));
+}
+
+#[test]
+fn test_viewport_subpixel_to_complex_point() {
+    let viewport = Viewport::from_corners(Complex { re: -1.0, im: 1.0 }, Complex { re: 1.0, im: -1.0 });
+
+    assert_eq!(
+        viewport.subpixel_to_complex_point((25.0, 75.0), 100, 100),
+        Complex { re: -0.5, im: -0.5 }
+    );
+}
+
+#[test]
+fn test_rotated_viewport() {
+    let mut viewport = Viewport::from_center_radius(Complex { re: 1.0, im: 1.0 }, 1.0).fit(200, 100);
+    let close_to = |a: Complex<f64>, b: Complex<f64>| (a - b).norm() < 1e-12;
+
+    viewport.rotation = std::f64::consts::FRAC_PI_2;
+
+    // Turned a quarter counterclockwise, the image's upper left corner, which
+    // would be 2 left of and 1 above the center, is 2 below and 1 left of it
+    assert!(close_to(
+        viewport.subpixel_to_complex_point((0.0, 0.0), 200, 100),
+        Complex { re: 0.0, im: -1.0 }
+    ));
+
+    // ...its middle stays put...
+    assert!(close_to(
+        viewport.subpixel_to_complex_point((100.0, 50.0), 200, 100),
+        Complex { re: 1.0, im: 1.0 }
+    ));
+
+    // ...and its top middle is to the left of the center
+    assert!(close_to(
+        viewport.subpixel_to_complex_point((100.0, 0.0), 200, 100),
+        Complex { re: 0.0, im: 1.0 }
+    ));
+
+    // A full turn is no turn at all
+    viewport.rotation = 2.0 * std::f64::consts::PI;
+
+    assert!(close_to(
+        viewport.subpixel_to_complex_point((0.0, 0.0), 200, 100),
+        Complex { re: -1.0, im: 2.0 }
+    ));
+}